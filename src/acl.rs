@@ -0,0 +1,84 @@
+use crate::config::Config;
+use reqwest::Client;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::{Component, Path, PathBuf};
+
+const ACL_RELEASE_URL: &str =
+    "https://github.com/atcoder/ac-library/releases/download/v1.5.1/ac-library.zip";
+
+async fn download_acl() -> anyhow::Result<Vec<u8>> {
+    println!("{} をダウンロードしています...", ACL_RELEASE_URL);
+    let client = Client::new();
+    let res = client.get(ACL_RELEASE_URL).send().await?;
+    if !res.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "HTTPリクエストが失敗しました: {}",
+            res.status()
+        ));
+    }
+    Ok(res.bytes().await?.to_vec())
+}
+
+fn atcoder_relative_path(entry_name: &str) -> Option<PathBuf> {
+    // ac-library-master/atcoder/dsu.hpp のようなパスから atcoder/dsu.hpp を取り出す
+    let path = Path::new(entry_name);
+    let components: Vec<Component> = path.components().collect();
+    if components
+        .iter()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+    let start = components.iter().position(|c| c.as_os_str() == "atcoder")?;
+    Some(components[start..].iter().collect())
+}
+
+pub async fn install(archive: Option<PathBuf>, config: &Config) -> anyhow::Result<PathBuf> {
+    /*
+    ACL の zip を展開し、atcoder/ 以下のヘッダを ACL のディレクトリに置く
+    args:
+        archive: 手元にある zip のパス (省略時は公式のリリースをダウンロードする)
+        config: atk の設定
+    returns:
+        ACL を展開したディレクトリ
+    */
+    let bytes = match archive {
+        Some(path) => {
+            if !path.exists() {
+                return Err(anyhow::anyhow!("{} が存在しません", path.display()));
+            }
+            fs::read(&path)?
+        }
+        None => download_acl().await?,
+    };
+
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| anyhow::anyhow!("zip の読み込みに失敗: {}", e))?;
+    let acl_dir = config.acl_dir()?;
+    let mut installed = 0;
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(relative) = atcoder_relative_path(entry.name()) else {
+            continue;
+        };
+        let dest = acl_dir.join(relative);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents)?;
+        fs::write(&dest, contents)?;
+        installed += 1;
+    }
+
+    if installed == 0 {
+        return Err(anyhow::anyhow!(
+            "zip に atcoder/ ディレクトリが含まれていません"
+        ));
+    }
+    Ok(acl_dir)
+}
@@ -0,0 +1,725 @@
+use crate::exec;
+use colored::Colorize;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Cursor, Read};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+// ----------------------------------------------  //
+// AHC (ヒューリスティックコンテスト) のローカル実行
+// コンテストのディレクトリは次の構成を想定する
+//   main.cpp          解答
+//   in/0000.txt ...   入力 (公式ツールの gen で生成したもの)
+//   out/0000.txt ...  atk ahc run が書き出す出力
+//   tools/            公式のローカルツール (vis で得点を計算する。対話型の問題では tester が解答を起動する)
+//   .atk/ahc.toml     ツールのパスなどコンテストごとの情報
+// ----------------------------------------------  //
+
+// 公式ツールの展開先
+const TOOLS_DIR: &str = "tools";
+// vis の実行ファイル (ahc.toml に記録がない場合に使う)
+const VIS_PATH: &str = "tools/target/release/vis";
+// tester の実行ファイルとソース (ソースがあれば対話型の問題とみなす)
+const TESTER_PATH: &str = "tools/target/release/tester";
+const TESTER_SOURCE: &str = "tools/src/bin/tester.rs";
+// コンテストごとの情報
+const META_PATH: &str = ".atk/ahc.toml";
+// vis が seed ごとに出力するファイルの置き場所 (vis は作業ディレクトリに vis.html を書く)
+pub const VIS_WORK_DIR: &str = ".atk/ahc/vis";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Objective {
+    // 得点が高いほど良い
+    #[default]
+    Maximize,
+    // 得点が低いほど良い
+    Minimize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContestMeta {
+    // コンテストのディレクトリからの相対パス
+    pub vis: Option<PathBuf>,
+    pub tester: Option<PathBuf>,
+    // 対話型の問題 (tester を通して解答を実行する)。None の場合は tools/ から推定する
+    pub interactive: Option<bool>,
+    pub objective: Objective,
+}
+
+impl ContestMeta {
+    pub fn load(contest_dir: &Path) -> anyhow::Result<Self> {
+        let path = contest_dir.join(META_PATH);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        toml::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| anyhow::anyhow!("{} の読み込みに失敗: {}", path.display(), e))
+    }
+
+    pub fn save(&self, contest_dir: &Path) -> anyhow::Result<()> {
+        let path = contest_dir.join(META_PATH);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+pub enum ToolsSource {
+    // 問題ページにある zip の URL (Cargo.toml を含むものが見つかるまで順に試す)
+    Urls(Vec<String>),
+    // 手元にある zip
+    Archive(PathBuf),
+}
+
+pub fn tool_archives(links: &[String]) -> Vec<String> {
+    /*
+    問題ページのリンクからローカル版のツールの zip を選び、試す順に並べる
+    Windows 向けのビルド済みの zip は Cargo.toml を含まないことが多いため後ろに回す
+    */
+    let mut archives: Vec<String> = links
+        .iter()
+        .filter(|link| link.ends_with(".zip"))
+        .cloned()
+        .collect();
+    archives.sort_by_key(|link| {
+        link.rsplit('/')
+            .next()
+            .is_some_and(|name| name.to_ascii_lowercase().contains("windows"))
+    });
+    archives
+}
+
+async fn download_tools(url: &str) -> anyhow::Result<Vec<u8>> {
+    println!("{} をダウンロードしています...", url);
+    let client = Client::new();
+    let res = client.get(url).send().await?;
+    if !res.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "HTTPリクエストが失敗しました: {}",
+            res.status()
+        ));
+    }
+    Ok(res.bytes().await?.to_vec())
+}
+
+fn extract_tools(bytes: Vec<u8>, tools_dir: &Path) -> anyhow::Result<()> {
+    /*
+    zip を tools_dir に展開する
+    zip の中身が1つのディレクトリ (tools/ など) にまとまっている場合はその中身を展開する
+    前回のツールのファイルが残らないよう、tools_dir は空にしてから展開する
+    */
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| anyhow::anyhow!("zip の読み込みに失敗: {}", e))?;
+    let names: Vec<PathBuf> = (0..zip.len())
+        .map(|i| {
+            zip.by_index(i)?
+                .enclosed_name()
+                .ok_or_else(|| anyhow::anyhow!("zip に不正なパスが含まれています"))
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let root = names
+        .first()
+        .and_then(|name| name.components().next())
+        .filter(|root| {
+            names
+                .iter()
+                .all(|name| name.components().next() == Some(*root))
+                && names.iter().any(|name| name.components().count() > 1)
+        });
+
+    let relatives: Vec<&Path> = names
+        .iter()
+        .map(|name| match root {
+            Some(root) => name.strip_prefix(root).unwrap_or(name),
+            None => name.as_path(),
+        })
+        .collect();
+    // 展開する前に確かめ、使えない zip で既存の tools/ を消さないようにする
+    if !relatives.contains(&Path::new("Cargo.toml")) {
+        return Err(anyhow::anyhow!(
+            "zip に Cargo.toml が含まれていません (Rust 版のツールを指定してください)"
+        ));
+    }
+    if tools_dir.exists() {
+        fs::remove_dir_all(tools_dir)?;
+    }
+
+    for (i, relative) in relatives.iter().enumerate() {
+        let mut entry = zip.by_index(i)?;
+        let dest = tools_dir.join(relative);
+        if entry.is_dir() {
+            fs::create_dir_all(&dest)?;
+            continue;
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents)?;
+        fs::write(&dest, contents)?;
+    }
+    Ok(())
+}
+
+fn build_tools(tools_dir: &Path) -> anyhow::Result<()> {
+    println!("公式ツールをビルドしています...");
+    let status = Command::new("cargo")
+        .args(["build", "--release"])
+        .current_dir(tools_dir)
+        .status()
+        .map_err(|e| anyhow::anyhow!("cargo の起動に失敗: {}", e))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("公式ツールのビルドに失敗しました"));
+    }
+    Ok(())
+}
+
+fn generate_inputs(contest_dir: &Path, generator: &Path) -> anyhow::Result<usize> {
+    /*
+    tools/seeds.txt から gen で in/ に入力を生成する
+    gen がない場合は zip に同梱された tools/in/ を使う
+    returns:
+        in/ にある入力の数
+    */
+    let tools_dir = contest_dir.join(TOOLS_DIR);
+    let seeds = tools_dir.join("seeds.txt");
+    let input_dir = contest_dir.join("in");
+    if generator.exists() && seeds.exists() {
+        let status = Command::new(generator)
+            .arg(&seeds)
+            .current_dir(contest_dir)
+            .status()
+            .map_err(|e| anyhow::anyhow!("{} の実行に失敗: {}", generator.display(), e))?;
+        if !status.success() {
+            return Err(anyhow::anyhow!("入力の生成に失敗しました"));
+        }
+    } else if tools_dir.join("in").is_dir() {
+        fs::create_dir_all(&input_dir)?;
+        for entry in fs::read_dir(tools_dir.join("in"))? {
+            let path = entry?.path();
+            if let Some(name) = path.file_name() {
+                fs::copy(&path, input_dir.join(name))?;
+            }
+        }
+    }
+    if !input_dir.is_dir() {
+        return Ok(0);
+    }
+    Ok(list_seeds(&input_dir)?.len())
+}
+
+pub async fn install_tools(contest_dir: &Path, source: ToolsSource) -> anyhow::Result<ContestMeta> {
+    /*
+    公式のローカルツールを tools/ に展開してビルドし、入力を生成する
+    args:
+        contest_dir: コンテストのディレクトリ
+        source: zip の入手先
+    returns:
+        ツールのパスを記録したコンテストの情報 (.atk/ahc.toml にも保存する)
+    */
+    let tools_dir = contest_dir.join(TOOLS_DIR);
+    match source {
+        ToolsSource::Urls(urls) => {
+            let mut errors = Vec::new();
+            for url in &urls {
+                match download_tools(url)
+                    .await
+                    .and_then(|bytes| extract_tools(bytes, &tools_dir))
+                {
+                    Err(e) => errors.push(format!("  {}: {}", url, e)),
+                    _ => break,
+                }
+            }
+            if errors.len() == urls.len() {
+                return Err(anyhow::anyhow!(
+                    "ローカル版のツールを展開できませんでした\n{}",
+                    errors.join("\n")
+                ));
+            }
+        }
+        ToolsSource::Archive(path) => {
+            if !path.exists() {
+                return Err(anyhow::anyhow!("{} が存在しません", path.display()));
+            }
+            extract_tools(fs::read(&path)?, &tools_dir)?;
+        }
+    }
+    build_tools(&tools_dir)?;
+
+    let release = Path::new(TOOLS_DIR).join("target").join("release");
+    let built = |name: &str| {
+        let path = release.join(name);
+        contest_dir.join(&path).exists().then_some(path)
+    };
+    let mut meta = ContestMeta::load(contest_dir)?;
+    meta.vis = built("vis");
+    meta.tester = built("tester");
+    meta.interactive = Some(contest_dir.join(TESTER_SOURCE).exists());
+    meta.save(contest_dir)?;
+
+    let count = generate_inputs(contest_dir, &contest_dir.join(release.join("gen")))?;
+    println!("in/ に {} 個の入力があります", count);
+    Ok(meta)
+}
+
+pub struct RunOptions {
+    // None の場合は in/ の全ての入力を実行する
+    pub seeds: Option<Range<u32>>,
+    pub time_limit: Duration,
+    pub jobs: usize,
+    // シードごとの結果を表示せず、合計だけを表示する
+    pub quiet: bool,
+}
+
+pub struct SeedResult {
+    pub seed: u32,
+    // 得点を得られなかった場合 (エラーや TLE) は None
+    pub score: Option<u64>,
+    pub elapsed: Duration,
+    pub error: Option<String>,
+}
+
+pub fn parse_seeds(s: &str) -> anyhow::Result<Range<u32>> {
+    /*
+    --seeds の値を解釈する ("0..100" は 0 から 99 まで、"5" は 5 のみ)
+    */
+    let parse = |n: &str| {
+        n.trim()
+            .parse::<u32>()
+            .map_err(|_| anyhow::anyhow!("シードは 0..100 や 5 の形式で指定してください"))
+    };
+    match s.split_once("..") {
+        Some((from, to)) => {
+            let (from, to) = (parse(from)?, parse(to)?);
+            if from >= to {
+                return Err(anyhow::anyhow!("範囲が空です: {}", s));
+            }
+            Ok(from..to)
+        }
+        None => {
+            let seed = parse(s)?;
+            Ok(seed..seed + 1)
+        }
+    }
+}
+
+pub fn seed_file(dir: &Path, seed: u32) -> PathBuf {
+    dir.join(format!("{:04}.txt", seed))
+}
+
+pub fn list_seeds(input_dir: &Path) -> anyhow::Result<Vec<u32>> {
+    // in/ にある 0000.txt のようなファイルのシードを昇順に並べる
+    let mut seeds: Vec<u32> = fs::read_dir(input_dir)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "txt" {
+                return None;
+            }
+            path.file_stem()?.to_str()?.parse().ok()
+        })
+        .collect();
+    seeds.sort_unstable();
+    Ok(seeds)
+}
+
+pub fn parse_score(text: &str) -> Option<u64> {
+    // 公式ツールが出力する "Score = 1234" の行から得点を読む
+    text.lines()
+        .rev()
+        .find_map(|line| line.trim().strip_prefix("Score = "))
+        .and_then(|score| score.trim().parse().ok())
+}
+
+pub fn vis_path(contest_dir: &Path) -> anyhow::Result<PathBuf> {
+    // .atk/ahc.toml に記録された vis (記録がなければ既定の場所)
+    let meta = ContestMeta::load(contest_dir)?;
+    let vis = contest_dir.join(meta.vis.unwrap_or_else(|| PathBuf::from(VIS_PATH)));
+    if !vis.exists() {
+        return Err(anyhow::anyhow!(
+            "{} が存在しません (atk ahc tools で公式ツールを準備してください)",
+            vis.display()
+        ));
+    }
+    Ok(vis)
+}
+
+pub fn score_output(vis: &Path, contest_dir: &Path, seed: u32) -> anyhow::Result<u64> {
+    /*
+    vis に入力と出力を渡して得点を計算する
+    vis が出力するファイル (vis.html など) は VIS_WORK_DIR/<seed>/ に置かれる
+    */
+    let work_dir = contest_dir.join(VIS_WORK_DIR).join(format!("{:04}", seed));
+    // 前回の実行で作られたファイルが残らないよう作り直す
+    if work_dir.exists() {
+        fs::remove_dir_all(&work_dir)?;
+    }
+    fs::create_dir_all(&work_dir)?;
+    let output = Command::new(vis)
+        .arg(seed_file(&contest_dir.join("in"), seed))
+        .arg(seed_file(&contest_dir.join("out"), seed))
+        .current_dir(&work_dir)
+        .output()
+        .map_err(|e| anyhow::anyhow!("{} の実行に失敗: {}", vis.display(), e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match parse_score(&stdout).or_else(|| parse_score(&stderr)) {
+        Some(score) => Ok(score),
+        None => {
+            let message = format!("{}{}", stdout, stderr);
+            Err(anyhow::anyhow!(
+                "得点を読み取れませんでした: {}",
+                message.trim()
+            ))
+        }
+    }
+}
+
+// 得点の計算方法
+enum Scorer {
+    // 解答の出力を vis に渡す
+    Vis(PathBuf),
+    // tester が解答を起動して対話し、標準エラー出力に得点を出力する
+    Tester(PathBuf),
+}
+
+fn scorer(contest_dir: &Path) -> anyhow::Result<Scorer> {
+    /*
+    .atk/ahc.toml に interactive が記録されていればそれに従い、
+    なければ tools/ に tester のソースがあるかで対話型の問題かを判断する
+    */
+    let meta = ContestMeta::load(contest_dir)?;
+    let interactive = meta
+        .interactive
+        .unwrap_or_else(|| contest_dir.join(TESTER_SOURCE).exists());
+    if !interactive {
+        return Ok(Scorer::Vis(vis_path(contest_dir)?));
+    }
+    let tester = contest_dir.join(meta.tester.unwrap_or_else(|| PathBuf::from(TESTER_PATH)));
+    if !tester.exists() {
+        return Err(anyhow::anyhow!(
+            "{} が存在しません (atk ahc tools で公式ツールを準備してください)",
+            tester.display()
+        ));
+    }
+    Ok(Scorer::Tester(tester))
+}
+
+fn tester_score(stderr: &[u8]) -> Result<u64, String> {
+    // tester の標準エラー出力から得点を読む (読めない場合は最後の行をエラーとする)
+    let stderr = String::from_utf8_lossy(stderr);
+    parse_score(&stderr).ok_or_else(|| {
+        let last = stderr.lines().rev().find(|line| !line.trim().is_empty());
+        format!(
+            "得点を読み取れませんでした: {}",
+            last.unwrap_or_default().trim()
+        )
+    })
+}
+
+fn run_seed(
+    executable: &Path,
+    scorer: &Scorer,
+    contest_dir: &Path,
+    seed: u32,
+    time_limit: Duration,
+) -> anyhow::Result<SeedResult> {
+    /*
+    1つのシードについて解答を実行し、出力を out/ に書き出して得点を計算する
+    対話型の問題では tester に解答を起動させ、tester の出力を out/ に書き出す
+    */
+    let input = fs::read_to_string(seed_file(&contest_dir.join("in"), seed))?;
+    let execution = match scorer {
+        Scorer::Vis(_) => exec::run_executable(executable, &input, Some(time_limit))?,
+        Scorer::Tester(tester) => {
+            let mut command = Command::new(tester);
+            command.arg(executable);
+            // 時間切れの際に tester が起動した解答もまとめて止められるよう、プロセスグループを分ける
+            #[cfg(unix)]
+            std::os::unix::process::CommandExt::process_group(&mut command, 0);
+            exec::run_command(command, &input, Some(time_limit))?
+        }
+    };
+    fs::write(
+        seed_file(&contest_dir.join("out"), seed),
+        &execution.output.stdout,
+    )?;
+
+    let mut result = SeedResult {
+        seed,
+        score: None,
+        elapsed: execution.elapsed,
+        error: None,
+    };
+    if execution.timed_out {
+        result.error = Some("実行時間制限を超えました".to_string());
+        return Ok(result);
+    }
+    match scorer {
+        Scorer::Vis(_) if !execution.output.status.success() => {
+            result.error = Some(exec::exit_description(&execution.output.status));
+        }
+        Scorer::Vis(vis) => match score_output(vis, contest_dir, seed) {
+            Ok(score) => result.score = Some(score),
+            Err(e) => result.error = Some(e.to_string()),
+        },
+        // 解答が不正な出力をした場合も tester は得点 (0 点) を出力することがある
+        Scorer::Tester(_) => match tester_score(&execution.output.stderr) {
+            Ok(score) => result.score = Some(score),
+            Err(e) => result.error = Some(e),
+        },
+    }
+    Ok(result)
+}
+
+pub fn run(
+    contest_dir: &Path,
+    executable: &Path,
+    options: &RunOptions,
+) -> anyhow::Result<Vec<SeedResult>> {
+    /*
+    in/ の入力で解答を並列に実行し、シードごとの得点を表示する
+    args:
+        contest_dir: コンテストのディレクトリ
+        executable: 解答の実行ファイル
+        options: 実行するシードなどのオプション
+    returns:
+        シードごとの結果 (シードの昇順)
+    */
+    let input_dir = contest_dir.join("in");
+    if !input_dir.is_dir() {
+        return Err(anyhow::anyhow!(
+            "{} が存在しません (atk ahc tools で入力を生成してください)",
+            input_dir.display()
+        ));
+    }
+    let scorer = scorer(contest_dir)?;
+    if let Scorer::Tester(tester) = &scorer {
+        println!(
+            "{}",
+            format!("対話型の問題として {} を通して実行します", tester.display()).dimmed()
+        );
+    }
+
+    let available = list_seeds(&input_dir)?;
+    let seeds: Vec<u32> = match &options.seeds {
+        Some(range) => {
+            let missing: Vec<u32> = range.clone().filter(|s| !available.contains(s)).collect();
+            if let Some(seed) = missing.first() {
+                return Err(anyhow::anyhow!(
+                    "{} が存在しません",
+                    seed_file(&input_dir, *seed).display()
+                ));
+            }
+            range.clone().collect()
+        }
+        None => available,
+    };
+    if seeds.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{} に入力ファイルがありません",
+            input_dir.display()
+        ));
+    }
+    fs::create_dir_all(contest_dir.join("out"))?;
+
+    let mut results = Vec::new();
+    exec::run_parallel(
+        seeds.len(),
+        options.jobs,
+        |i| {
+            run_seed(
+                executable,
+                &scorer,
+                contest_dir,
+                seeds[i],
+                options.time_limit,
+            )
+        },
+        |_, result| {
+            let result = result?;
+            if !options.quiet {
+                print_seed(&result);
+            }
+            results.push(result);
+            Ok(())
+        },
+    )?;
+    print_summary(&results);
+    Ok(results)
+}
+
+fn print_seed(result: &SeedResult) {
+    let time = format!("{}ms", result.elapsed.as_millis());
+    match (result.score, &result.error) {
+        (Some(score), _) => println!("{:04}: {:>12} ({})", result.seed, score, time.dimmed()),
+        (None, error) => println!(
+            "{:04}: {:>12} ({}) {}",
+            result.seed,
+            "-".red(),
+            time.dimmed(),
+            error.as_deref().unwrap_or_default().red()
+        ),
+    }
+}
+
+fn print_summary(results: &[SeedResult]) {
+    let total: u64 = results.iter().filter_map(|r| r.score).sum();
+    let failed = results.iter().filter(|r| r.score.is_none()).count();
+    let max_time = results.iter().map(|r| r.elapsed).max().unwrap_or_default();
+    let mut line = format!(
+        "total {}, mean {:.1} ({} seeds), max time {}ms",
+        total,
+        total as f64 / results.len() as f64,
+        results.len(),
+        max_time.as_millis()
+    );
+    if failed > 0 {
+        line.push_str(&format!(", {} failed", failed));
+        println!("{}", line.red().bold());
+    } else {
+        println!("{}", line.green().bold());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_seed_ranges() {
+        let cases = [
+            ("0..100", Some(0..100)),
+            (" 3 .. 5 ", Some(3..5)),
+            ("5", Some(5..6)),
+            ("10..10", None),
+            ("10..3", None),
+            ("-1..3", None),
+            ("a", None),
+            ("0..", None),
+        ];
+        for (s, expected) in cases {
+            assert_eq!(parse_seeds(s).ok(), expected, "{}", s);
+        }
+    }
+
+    #[test]
+    fn parses_scores() {
+        let cases = [
+            ("Score = 1234\n", Some(1234)),
+            ("turn 10\n  Score = 42  \n", Some(42)),
+            // 複数ある場合は最後の行を使う
+            ("Score = 1\nScore = 2\n", Some(2)),
+            ("Score = -1\n", None),
+            ("score: 100\n", None),
+            ("", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_score(text), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn prefers_archives_with_sources() {
+        let links: Vec<String> = [
+            "https://img.atcoder.jp/ahc001/tools_x86_64-pc-windows-gnu.zip",
+            "https://img.atcoder.jp/ahc001/a.html",
+            "https://img.atcoder.jp/ahc001/tools.zip",
+        ]
+        .map(String::from)
+        .to_vec();
+        assert_eq!(
+            tool_archives(&links),
+            [
+                "https://img.atcoder.jp/ahc001/tools.zip",
+                "https://img.atcoder.jp/ahc001/tools_x86_64-pc-windows-gnu.zip",
+            ]
+        );
+    }
+
+    fn zip_of(files: &[(&str, &str)]) -> Vec<u8> {
+        use std::io::Write;
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn replaces_tools_directory() {
+        let dir = std::env::temp_dir().join(format!("atk-ahc-tools-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("old.txt"), "").unwrap();
+
+        // Cargo.toml を含まない zip では既存のファイルを残す
+        let binaries = zip_of(&[("vis.exe", "")]);
+        assert!(extract_tools(binaries, &dir).is_err());
+        assert!(dir.join("old.txt").exists());
+
+        let sources = zip_of(&[("tools/Cargo.toml", "[package]"), ("tools/src/lib.rs", "")]);
+        extract_tools(sources, &dir).unwrap();
+        let exists = |name: &str| dir.join(name).exists();
+        let result = (
+            exists("Cargo.toml"),
+            exists("src/lib.rs"),
+            exists("old.txt"),
+        );
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(result, (true, true, false));
+    }
+
+    #[test]
+    fn follows_recorded_interactive_flag() {
+        let dir = std::env::temp_dir().join(format!("atk-ahc-scorer-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for path in [TESTER_SOURCE, VIS_PATH, TESTER_PATH] {
+            fs::create_dir_all(dir.join(path).parent().unwrap()).unwrap();
+            fs::write(dir.join(path), "").unwrap();
+        }
+        let interactive = |recorded: Option<bool>| {
+            let meta = ContestMeta {
+                interactive: recorded,
+                ..ContestMeta::default()
+            };
+            meta.save(&dir).unwrap();
+            matches!(scorer(&dir).unwrap(), Scorer::Tester(_))
+        };
+        // 記録がなければ tester のソースがあるかで判断する
+        let results = [
+            interactive(None),
+            interactive(Some(false)),
+            interactive(Some(true)),
+        ];
+        fs::remove_file(dir.join(TESTER_SOURCE)).unwrap();
+        let without_source = [interactive(None), interactive(Some(true))];
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(results, [true, false, true]);
+        assert_eq!(without_source, [false, true]);
+    }
+
+    #[test]
+    fn lists_seed_files() {
+        let dir = std::env::temp_dir().join(format!("atk-ahc-seeds-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["0002.txt", "0000.txt", "0010.txt", "note.txt", "0001.out"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let seeds = list_seeds(&dir).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(seeds, vec![0, 2, 10]);
+        assert_eq!(seed_file(Path::new("in"), 7), Path::new("in/0007.txt"));
+    }
+}
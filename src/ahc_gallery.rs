@@ -0,0 +1,247 @@
+use crate::ahc;
+use crate::exec;
+use std::fmt::Write;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+// ----------------------------------------------  //
+// AHC のビジュアライザの一覧
+// out/ の出力を公式ツールの vis に渡し、シードごとに出力された SVG や HTML を
+// .atk/ahc/gallery.html にまとめて、ブラウザでまとめて見られるようにする
+// ----------------------------------------------  //
+
+const GALLERY_PATH: &str = ".atk/ahc/gallery.html";
+
+struct Entry {
+    seed: u32,
+    score: Result<u64, String>,
+    // gallery.html からの相対パス
+    files: Vec<String>,
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn rendered_files(contest_dir: &Path, seed: u32) -> anyhow::Result<Vec<String>> {
+    // vis がシードの作業ディレクトリに出力したファイルを、gallery.html からの相対パスで返す
+    let relative = Path::new(ahc::VIS_WORK_DIR).join(format!("{:04}", seed));
+    let gallery_dir = Path::new(GALLERY_PATH).parent().unwrap_or(Path::new(""));
+    let mut files: Vec<String> = fs::read_dir(contest_dir.join(&relative))?
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().into_string().ok()?;
+            let path = relative.join(name);
+            Some(path.strip_prefix(gallery_dir).ok()?.display().to_string())
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn preview(file: &str) -> Option<String> {
+    // ブラウザで埋め込めるファイルの表示
+    let lower = file.to_ascii_lowercase();
+    let file = escape_html(file);
+    if lower.ends_with(".html") || lower.ends_with(".htm") {
+        Some(format!(
+            r#"<iframe src="{}" loading="lazy"></iframe>"#,
+            file
+        ))
+    } else if [".svg", ".png", ".gif", ".jpg", ".jpeg"]
+        .iter()
+        .any(|ext| lower.ends_with(ext))
+    {
+        Some(format!(r#"<img src="{}" loading="lazy">"#, file))
+    } else {
+        None
+    }
+}
+
+fn render_html(contest_name: &str, entries: &[Entry]) -> String {
+    let mut cards = String::new();
+    for entry in entries {
+        let score = match &entry.score {
+            Ok(score) => format!("score {}", score),
+            Err(e) => format!(r#"<span class="error">{}</span>"#, escape_html(e)),
+        };
+        let links: Vec<String> = entry
+            .files
+            .iter()
+            .map(|file| {
+                let name = file.rsplit('/').next().unwrap_or(file);
+                format!(
+                    r#"<a href="{}" target="_blank">{}</a>"#,
+                    escape_html(file),
+                    escape_html(name)
+                )
+            })
+            .collect();
+        let preview = entry
+            .files
+            .iter()
+            .find_map(|file| preview(file))
+            .unwrap_or_else(|| {
+                r#"<p class="error">表示できるファイルがありません</p>"#.to_string()
+            });
+        // 文字列への書き込みは失敗しない
+        let _ = write!(
+            cards,
+            r#"<div class="card" id="seed{seed:04}">
+<div class="header"><b>{seed:04}</b> {score} {links}</div>
+{preview}
+</div>
+"#,
+            seed = entry.seed,
+            score = score,
+            links = links.join(" "),
+            preview = preview
+        );
+    }
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; margin: 16px; }}
+.grid {{ display: grid; grid-template-columns: repeat(auto-fill, minmax(420px, 1fr)); gap: 12px; }}
+.card {{ border: 1px solid #ccc; border-radius: 4px; padding: 8px; }}
+.header {{ margin-bottom: 6px; }}
+.header a {{ margin-left: 8px; }}
+.error {{ color: #c00; }}
+iframe, img {{ width: 100%; height: 420px; border: none; object-fit: contain; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<div class="grid">
+{cards}</div>
+</body>
+</html>
+"#,
+        title = escape_html(&format!("{} visualizer", contest_name)),
+        cards = cards
+    )
+}
+
+pub fn render(
+    contest_dir: &Path,
+    seeds: Option<Range<u32>>,
+    jobs: usize,
+) -> anyhow::Result<PathBuf> {
+    /*
+    out/ の出力を vis に渡し、シードごとの可視化を1つの HTML にまとめる
+    args:
+        contest_dir: コンテストのディレクトリ
+        seeds: 表示するシード (None の場合は out/ の全ての出力)
+        jobs: 同時に実行する vis の数
+    returns:
+        作成した HTML のパス
+    */
+    let vis = ahc::vis_path(contest_dir)?;
+    let output_dir = contest_dir.join("out");
+    if !output_dir.is_dir() {
+        return Err(anyhow::anyhow!(
+            "{} が存在しません (先に atk ahc run を実行してください)",
+            output_dir.display()
+        ));
+    }
+    let input_dir = contest_dir.join("in");
+    let seeds: Vec<u32> = ahc::list_seeds(&output_dir)?
+        .into_iter()
+        .filter(|seed| seeds.as_ref().is_none_or(|range| range.contains(seed)))
+        .filter(|&seed| ahc::seed_file(&input_dir, seed).exists())
+        .collect();
+    if seeds.is_empty() {
+        return Err(anyhow::anyhow!("表示する出力がありません"));
+    }
+
+    let mut entries = Vec::new();
+    exec::run_parallel(
+        seeds.len(),
+        jobs,
+        |i| ahc::score_output(&vis, contest_dir, seeds[i]),
+        |i, score| {
+            entries.push(Entry {
+                seed: seeds[i],
+                score: score.map_err(|e| e.to_string()),
+                files: rendered_files(contest_dir, seeds[i])?,
+            });
+            Ok(())
+        },
+    )?;
+
+    let contest_name = contest_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let path = contest_dir.join(GALLERY_PATH);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, render_html(&contest_name, &entries))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_html() {
+        let cases = [
+            ("plain", "plain"),
+            ("a < b && c > d", "a &lt; b &amp;&amp; c &gt; d"),
+            (r#"say "hi""#, "say &quot;hi&quot;"),
+            ("&lt;", "&amp;lt;"),
+            ("", ""),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(escape_html(text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn previews_embeddable_files() {
+        let cases = [
+            (
+                "vis/0000/vis.html",
+                Some(r#"<iframe src="vis/0000/vis.html""#),
+            ),
+            ("vis/0000/OUT.SVG", Some(r#"<img src="vis/0000/OUT.SVG""#)),
+            (
+                r#"vis/0000/a"b.png"#,
+                Some(r#"<img src="vis/0000/a&quot;b.png""#),
+            ),
+            ("vis/0000/log.txt", None),
+        ];
+        for (file, expected) in cases {
+            let html = preview(file);
+            assert_eq!(
+                html.as_deref().map(|h| &h[..h.find(" loading").unwrap()]),
+                expected,
+                "{}",
+                file
+            );
+        }
+    }
+
+    #[test]
+    fn escapes_errors_in_gallery() {
+        let entries = [Entry {
+            seed: 3,
+            score: Err("<script>".to_string()),
+            files: Vec::new(),
+        }];
+        let html = render_html("ahc001", &entries);
+        assert!(html.contains(r#"id="seed0003""#));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("<script>"));
+    }
+}
@@ -0,0 +1,540 @@
+use crate::ahc::{Objective, SeedResult};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+// ----------------------------------------------  //
+// AHC の実行履歴
+// atk ahc run の結果を .atk/ahc/runs.jsonl に1行1回分の JSON として追記し、
+// atk ahc compare で2回分の結果をシードごとに比べ、
+// atk ahc rank で複数回分の結果を公式のシステムテストと同じ相対評価で順位付けする
+// ----------------------------------------------  //
+
+const RUNS_PATH: &str = ".atk/ahc/runs.jsonl";
+// 相対評価で、シードごとの最良の得点に与える点数
+const RELATIVE_SCALE: f64 = 1e9;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeedScore {
+    pub seed: u32,
+    // 得点を得られなかった場合は None
+    pub score: Option<u64>,
+    pub time_ms: u128,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RunRecord {
+    // 1 から順に振る番号
+    pub id: usize,
+    // UNIX 時間 (秒)
+    pub timestamp: u64,
+    // git のコミット (main.cpp に未コミットの変更がある場合は末尾に "-dirty")
+    pub commit: Option<String>,
+    // main.cpp の内容のハッシュ
+    pub source_hash: String,
+    pub label: Option<String>,
+    pub scores: Vec<SeedScore>,
+}
+
+impl RunRecord {
+    pub fn score(&self, seed: u32) -> Option<Option<u64>> {
+        // シードを実行していない場合は None、実行したが得点がない場合は Some(None)
+        self.scores.iter().find(|s| s.seed == seed).map(|s| s.score)
+    }
+
+    pub fn total(&self) -> u64 {
+        self.scores.iter().filter_map(|s| s.score).sum()
+    }
+
+    pub fn describe(&self) -> String {
+        // "#3 (2025-01-02 03:04 UTC, abc1234, 焼きなまし)" のような説明
+        let mut parts = vec![format_timestamp(self.timestamp)];
+        match &self.commit {
+            Some(commit) => parts.push(commit.clone()),
+            None => parts.push(format!(
+                "hash {}",
+                self.source_hash.get(..8).unwrap_or(&self.source_hash)
+            )),
+        }
+        if let Some(label) = &self.label {
+            parts.push(label.clone());
+        }
+        format!("#{} ({})", self.id, parts.join(", "))
+    }
+}
+
+fn format_timestamp(timestamp: u64) -> String {
+    /*
+    UNIX 時間を "YYYY-MM-DD hh:mm UTC" に変換する
+    */
+    let days = (timestamp / 86400) as i64;
+    let (hour, minute) = (timestamp % 86400 / 3600, timestamp % 3600 / 60);
+    // 1970-01-01 からの日数をグレゴリオ暦の日付にする
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year, month, day, hour, minute
+    )
+}
+
+fn git_commit(contest_dir: &Path, source: &Path) -> Option<String> {
+    // git の管理下にない場合は None
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(contest_dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let status = Command::new("git")
+        .args(["status", "--porcelain", "--"])
+        .arg(source)
+        .current_dir(contest_dir)
+        .output()
+        .ok()?;
+    if status.stdout.is_empty() {
+        Some(commit)
+    } else {
+        Some(format!("{}-dirty", commit))
+    }
+}
+
+pub fn load_runs(contest_dir: &Path) -> anyhow::Result<Vec<RunRecord>> {
+    let path = contest_dir.join(RUNS_PATH);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    fs::read_to_string(&path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                anyhow::anyhow!(
+                    "{} の {} 行目の読み込みに失敗: {}",
+                    path.display(),
+                    i + 1,
+                    e
+                )
+            })
+        })
+        .collect()
+}
+
+pub fn find_run(runs: &[RunRecord], id: usize) -> anyhow::Result<&RunRecord> {
+    runs.iter()
+        .find(|run| run.id == id)
+        .ok_or_else(|| anyhow::anyhow!("実行 #{} が見つかりません", id))
+}
+
+pub struct SourceVersion {
+    pub commit: Option<String>,
+    pub source_hash: String,
+}
+
+pub fn source_version(contest_dir: &Path, source: &Path) -> anyhow::Result<SourceVersion> {
+    /*
+    解答のソースのハッシュと git のコミットを求める
+    実行中にソースが編集されても実行したものを記録できるよう、コンパイルの前に呼ぶ
+    */
+    let mut hasher = DefaultHasher::new();
+    fs::read(source)?.hash(&mut hasher);
+    Ok(SourceVersion {
+        commit: git_commit(contest_dir, source),
+        source_hash: format!("{:016x}", hasher.finish()),
+    })
+}
+
+pub fn record_run(
+    contest_dir: &Path,
+    version: SourceVersion,
+    label: Option<String>,
+    results: &[SeedResult],
+) -> anyhow::Result<RunRecord> {
+    /*
+    atk ahc run の結果を履歴に追記する
+    args:
+        contest_dir: コンテストのディレクトリ
+        version: コンパイルする前に求めた解答のソースのハッシュとコミット
+        label: 実行につける説明
+        results: シードごとの結果
+    returns:
+        追記した記録
+    */
+    let id = load_runs(contest_dir)?.last().map_or(1, |run| run.id + 1);
+    let record = RunRecord {
+        id,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs(),
+        commit: version.commit,
+        source_hash: version.source_hash,
+        label,
+        scores: results
+            .iter()
+            .map(|r| SeedScore {
+                seed: r.seed,
+                score: r.score,
+                time_ms: r.elapsed.as_millis(),
+            })
+            .collect(),
+    };
+
+    let path = contest_dir.join(RUNS_PATH);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(&record)?)?;
+    Ok(record)
+}
+
+pub fn print_runs(runs: &[RunRecord]) {
+    for run in runs {
+        let failed = run.scores.iter().filter(|s| s.score.is_none()).count();
+        let mut line = format!(
+            "{}  total {} ({} seeds)",
+            run.describe(),
+            run.total(),
+            run.scores.len()
+        );
+        if failed > 0 {
+            line.push_str(&format!(", {} failed", failed).red().to_string());
+        }
+        println!("{}", line);
+    }
+}
+
+fn relative_score(base: Option<u64>, target: Option<u64>, objective: Objective) -> f64 {
+    // target が base の何倍良いか (1.0 より大きければ改善。得点を得られなかった方が悪いとする)
+    let (base, target) = match (base, target) {
+        (None, None) => return 1.0,
+        (Some(_), None) => return 0.0,
+        (None, Some(_)) => return f64::INFINITY,
+        (Some(base), Some(target)) => (base, target),
+    };
+    let (better, worse) = match objective {
+        Objective::Maximize => (target, base),
+        Objective::Minimize => (base, target),
+    };
+    if worse == 0 {
+        return if better == 0 { 1.0 } else { f64::INFINITY };
+    }
+    better as f64 / worse as f64
+}
+
+fn format_score(score: Option<u64>) -> String {
+    score.map_or("-".to_string(), |score| score.to_string())
+}
+
+fn format_ratio(ratio: f64) -> String {
+    if ratio.is_infinite() {
+        return "+inf".to_string();
+    }
+    format!("{:+.2}%", (ratio - 1.0) * 100.0)
+}
+
+pub fn compare(base: &RunRecord, target: &RunRecord, objective: Objective) {
+    /*
+    2回分の実行結果をシードごとに比べる
+    args:
+        base: 比較の基準にする実行
+        target: 比べる実行
+        objective: 得点を最大化する問題か最小化する問題か
+    */
+    println!("base:   {}", base.describe());
+    println!("target: {}", target.describe());
+
+    let mut ratios = Vec::new();
+    let mut regressions = Vec::new();
+    for seed_score in &base.scores {
+        let Some(target_score) = target.score(seed_score.seed) else {
+            continue;
+        };
+        let ratio = relative_score(seed_score.score, target_score, objective);
+        let change = format_ratio(ratio);
+        let change = if ratio > 1.0 {
+            change.green()
+        } else if ratio < 1.0 {
+            regressions.push((seed_score.seed, ratio));
+            change.red()
+        } else {
+            change.dimmed()
+        };
+        println!(
+            "{:04}: {:>12} -> {:>12} {}",
+            seed_score.seed,
+            format_score(seed_score.score),
+            format_score(target_score),
+            change
+        );
+        ratios.push(ratio);
+    }
+    if ratios.is_empty() {
+        println!("共通のシードがありません");
+        return;
+    }
+
+    // 倍率が無限大になるシード (base だけ得点がないなど) は平均と中央値から除く
+    let mut finite: Vec<f64> = ratios.iter().copied().filter(|r| r.is_finite()).collect();
+    finite.sort_by(|a, b| a.total_cmp(b));
+    let mean = finite.iter().sum::<f64>() / finite.len().max(1) as f64;
+    let median = match finite.len() {
+        0 => 1.0,
+        n if n % 2 == 1 => finite[n / 2],
+        n => (finite[n / 2 - 1] + finite[n / 2]) / 2.0,
+    };
+    let improved = ratios.iter().filter(|&&r| r > 1.0).count();
+    let line = format!(
+        "{} seeds: mean {}, median {} ({} improved, {} regressed, {} unchanged)",
+        ratios.len(),
+        format_ratio(mean),
+        format_ratio(median),
+        improved,
+        regressions.len(),
+        ratios.len() - improved - regressions.len()
+    );
+    if mean >= 1.0 {
+        println!("{}", line.green().bold());
+    } else {
+        println!("{}", line.red().bold());
+    }
+
+    if !regressions.is_empty() {
+        regressions.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let worst: Vec<String> = regressions
+            .iter()
+            .take(10)
+            .map(|(seed, ratio)| format!("{:04} ({})", seed, format_ratio(*ratio)))
+            .collect();
+        println!("{} {}", "regressions:".red().bold(), worst.join(", "));
+    }
+}
+
+fn relative_points(score: Option<u64>, best: u64, objective: Objective) -> u64 {
+    /*
+    公式のシステムテストと同じく、最良の得点を RELATIVE_SCALE としたときの点数を返す
+    (最大化なら round(scale * score / best)、最小化なら round(scale * best / score))
+    */
+    let Some(score) = score else {
+        return 0;
+    };
+    let ratio = match objective {
+        Objective::Maximize if best == 0 => 0.0,
+        Objective::Maximize => score as f64 / best as f64,
+        Objective::Minimize if score == 0 => 1.0,
+        Objective::Minimize => best as f64 / score as f64,
+    };
+    (RELATIVE_SCALE * ratio).round() as u64
+}
+
+pub fn rank(runs: &[&RunRecord], objective: Objective) -> anyhow::Result<()> {
+    /*
+    複数回分の実行結果を、全ての実行で共通するシードについて相対評価で順位付けする
+    args:
+        runs: 比べる実行
+        objective: 得点を最大化する問題か最小化する問題か
+    */
+    if runs.is_empty() {
+        return Err(anyhow::anyhow!("比べる実行がありません"));
+    }
+    let seeds: Vec<u32> = runs[0]
+        .scores
+        .iter()
+        .map(|s| s.seed)
+        .filter(|&seed| runs.iter().all(|run| run.score(seed).is_some()))
+        .collect();
+    if seeds.is_empty() {
+        return Err(anyhow::anyhow!("全ての実行で共通するシードがありません"));
+    }
+
+    // シードごとの最良の得点 (どの実行も得点を得られなかったシードは None)
+    let best: Vec<Option<u64>> = seeds
+        .iter()
+        .map(|&seed| {
+            let scores = runs.iter().filter_map(|run| run.score(seed).flatten());
+            match objective {
+                Objective::Maximize => scores.max(),
+                Objective::Minimize => scores.min(),
+            }
+        })
+        .collect();
+
+    struct Row<'a> {
+        run: &'a RunRecord,
+        relative: u64,
+        best_count: usize,
+        failed: usize,
+    }
+    let mut rows: Vec<Row> = runs
+        .iter()
+        .map(|run| {
+            let mut row = Row {
+                run,
+                relative: 0,
+                best_count: 0,
+                failed: 0,
+            };
+            for (&seed, best) in seeds.iter().zip(&best) {
+                let score = run.score(seed).flatten();
+                if score.is_none() {
+                    row.failed += 1;
+                }
+                if let Some(best) = best {
+                    row.relative += relative_points(score, *best, objective);
+                    row.best_count += usize::from(score == Some(*best));
+                }
+            }
+            row
+        })
+        .collect();
+    rows.sort_by_key(|row| std::cmp::Reverse(row.relative));
+
+    println!(
+        "{} seeds で比較 (シードごとの最良の得点を {} 点とする)",
+        seeds.len(),
+        RELATIVE_SCALE as u64
+    );
+    let full = RELATIVE_SCALE * seeds.len() as f64;
+    for (i, row) in rows.iter().enumerate() {
+        let mut line = format!(
+            "{:>2}. {:>14} ({:>6.2}%)  best {:>3}/{}",
+            i + 1,
+            row.relative,
+            row.relative as f64 / full * 100.0,
+            row.best_count,
+            seeds.len()
+        );
+        if row.failed > 0 {
+            line.push_str(&format!(", {} failed", row.failed).red().to_string());
+        }
+        let line = format!("{}  {}", line, row.run.describe());
+        if i == 0 {
+            println!("{}", line.green().bold());
+        } else {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_timestamps() {
+        let cases = [
+            (0, "1970-01-01 00:00 UTC"),
+            (951_782_400, "2000-02-29 00:00 UTC"),
+            (1_735_787_040, "2025-01-02 03:04 UTC"),
+            (4_107_542_399, "2100-02-28 23:59 UTC"),
+        ];
+        for (timestamp, expected) in cases {
+            assert_eq!(format_timestamp(timestamp), expected, "{}", timestamp);
+        }
+    }
+
+    #[test]
+    fn compares_scores_by_objective() {
+        use Objective::{Maximize, Minimize};
+        let cases = [
+            (Some(100), Some(150), Maximize, 1.5),
+            (Some(100), Some(50), Maximize, 0.5),
+            (Some(100), Some(50), Minimize, 2.0),
+            (Some(100), Some(200), Minimize, 0.5),
+            (Some(0), Some(0), Maximize, 1.0),
+            (Some(0), Some(5), Maximize, f64::INFINITY),
+            (Some(5), Some(0), Minimize, f64::INFINITY),
+            // 得点を得られなかった方が悪い
+            (None, None, Maximize, 1.0),
+            (Some(100), None, Maximize, 0.0),
+            (None, Some(100), Minimize, f64::INFINITY),
+        ];
+        for (base, target, objective, expected) in cases {
+            assert_eq!(
+                relative_score(base, target, objective),
+                expected,
+                "{:?} -> {:?}",
+                base,
+                target
+            );
+        }
+    }
+
+    #[test]
+    fn gives_relative_points() {
+        use Objective::{Maximize, Minimize};
+        let cases = [
+            (Some(100), 100, Maximize, 1_000_000_000),
+            (Some(50), 100, Maximize, 500_000_000),
+            (Some(1), 3, Maximize, 333_333_333),
+            (Some(0), 0, Maximize, 0),
+            (Some(200), 100, Minimize, 500_000_000),
+            (Some(3), 2, Minimize, 666_666_667),
+            (Some(0), 0, Minimize, 1_000_000_000),
+            (None, 100, Maximize, 0),
+            (None, 100, Minimize, 0),
+        ];
+        for (score, best, objective, expected) in cases {
+            assert_eq!(
+                relative_points(score, best, objective),
+                expected,
+                "{:?} / {}",
+                score,
+                best
+            );
+        }
+    }
+
+    #[test]
+    fn describes_runs_with_short_hashes() {
+        let run = |commit: Option<&str>, source_hash: &str| RunRecord {
+            id: 3,
+            timestamp: 1_735_787_040,
+            commit: commit.map(String::from),
+            source_hash: source_hash.to_string(),
+            label: Some("焼きなまし".to_string()),
+            scores: Vec::new(),
+        };
+        let cases = [
+            (
+                run(Some("abc1234"), "0123456789abcdef"),
+                "#3 (2025-01-02 03:04 UTC, abc1234, 焼きなまし)",
+            ),
+            (
+                run(None, "0123456789abcdef"),
+                "#3 (2025-01-02 03:04 UTC, hash 01234567, 焼きなまし)",
+            ),
+            // 手で編集された短いハッシュ
+            (
+                run(None, "0123"),
+                "#3 (2025-01-02 03:04 UTC, hash 0123, 焼きなまし)",
+            ),
+            (
+                run(None, "頂点"),
+                "#3 (2025-01-02 03:04 UTC, hash 頂点, 焼きなまし)",
+            ),
+        ];
+        for (run, expected) in cases {
+            assert_eq!(run.describe(), expected);
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// ----------------------------------------------  //
+// #include の展開
+// 提出先で使えないヘッダを中身で置き換え、1つのソースにまとめる
+// - #include "..." はソースと同じディレクトリ、設定されたライブラリのパスの順に探す
+// - #include <atcoder/...> は ACL を展開する場合のみ置き換える
+// #pragma once やインクルードガードを持つヘッダは2回目以降展開しない
+// それ以外のヘッダが互いに読み込み合っている場合はエラーにする
+// ----------------------------------------------  //
+
+pub struct BundleOptions<'a> {
+    pub library_paths: &'a [PathBuf],
+    // Some の場合は ACL も展開する
+    pub acl_dir: Option<&'a Path>,
+}
+
+pub fn parse_include(line: &str) -> Option<(&str, bool)> {
+    /*
+    #include <xxx> もしくは #include "xxx" を解釈する
+    returns:
+        (xxx, "..." で書かれているかどうか)
+    */
+    let rest = line.trim_start().strip_prefix('#')?;
+    let rest = rest.trim_start().strip_prefix("include")?.trim();
+    let (open, close) = match rest.chars().next()? {
+        '<' => ('<', '>'),
+        '"' => ('"', '"'),
+        _ => return None,
+    };
+    let (name, _) = rest.strip_prefix(open)?.split_once(close)?;
+    Some((name, open == '"'))
+}
+
+pub fn resolve_quoted(name: &str, dir: &Path, library_paths: &[PathBuf]) -> Option<PathBuf> {
+    // #include "name" が指すファイルを、読み込み元のディレクトリ、ライブラリのパスの順に探す
+    std::iter::once(dir)
+        .chain(library_paths.iter().map(PathBuf::as_path))
+        .map(|d| d.join(name))
+        .find(|p| p.is_file())
+}
+
+fn include_guard(source: &str) -> Option<String> {
+    // 先頭の #ifndef X / #define X からインクルードガードのマクロ名を取り出す
+    let mut lines = source
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with("//"));
+    let guard = lines.next()?.strip_prefix("#ifndef")?.trim();
+    let define = lines.next()?.strip_prefix("#define")?.trim();
+    (!guard.is_empty() && guard == define).then(|| guard.to_string())
+}
+
+fn is_pragma_once(line: &str) -> bool {
+    line.trim_start()
+        .strip_prefix('#')
+        .is_some_and(|rest| rest.split_whitespace().eq(["pragma", "once"]))
+}
+
+struct Expander<'a> {
+    options: &'a BundleOptions<'a>,
+    included: HashSet<PathBuf>,
+    guards: HashSet<String>,
+    // 展開中のファイル (循環の検出に使う)
+    stack: Vec<PathBuf>,
+}
+
+impl Expander<'_> {
+    fn expand_file(&mut self, path: &Path, out: &mut String) -> anyhow::Result<()> {
+        let path = path.canonicalize()?;
+        if self.included.contains(&path) {
+            return Ok(());
+        }
+        let source = fs::read_to_string(&path)?;
+        let pragma_once = source.lines().any(is_pragma_once);
+        let guard = include_guard(&source);
+        if let Some(guard) = &guard
+            && !self.guards.insert(guard.clone())
+        {
+            return Ok(());
+        }
+        // 何度読み込んでも同じ内容になるヘッダのみ重複を取り除く
+        if pragma_once || guard.is_some() {
+            self.included.insert(path.clone());
+        }
+        if let Some(start) = self.stack.iter().position(|p| *p == path) {
+            let cycle: Vec<String> = self.stack[start..]
+                .iter()
+                .chain(std::iter::once(&path))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(anyhow::anyhow!(
+                "#include が循環しています: {}",
+                cycle.join(" -> ")
+            ));
+        }
+        let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        self.stack.push(path);
+        self.expand(&source, &dir, out)?;
+        self.stack.pop();
+        Ok(())
+    }
+
+    fn expand(&mut self, source: &str, dir: &Path, out: &mut String) -> anyhow::Result<()> {
+        for line in source.lines() {
+            if is_pragma_once(line) {
+                continue;
+            }
+            let Some((name, quoted)) = parse_include(line) else {
+                out.push_str(line);
+                out.push('\n');
+                continue;
+            };
+            if let Some(acl_dir) = self.options.acl_dir
+                && name.starts_with("atcoder/")
+            {
+                let path = acl_dir.join(name);
+                if !path.is_file() {
+                    return Err(anyhow::anyhow!(
+                        "ACL のヘッダ {} が見つかりません ({})",
+                        name,
+                        path.display()
+                    ));
+                }
+                self.expand_file(&path, out)?;
+                continue;
+            }
+            match quoted
+                .then(|| resolve_quoted(name, dir, self.options.library_paths))
+                .flatten()
+            {
+                Some(path) => self.expand_file(&path, out)?,
+                // システムのヘッダなどはそのまま残す
+                None => {
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn bundle(source_path: &Path, options: &BundleOptions) -> anyhow::Result<String> {
+    /*
+    ソース中のローカルな #include (と ACL) を再帰的に展開し、1つのソースにまとめる
+    args:
+        source_path: 展開するソースファイル
+        options: ライブラリのパスと ACL の展開の有無
+    returns:
+        展開後のソース
+    */
+    let source = fs::read_to_string(source_path)?;
+    let source_path = source_path.canonicalize()?;
+    let dir = source_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let mut expander = Expander {
+        options,
+        included: HashSet::new(),
+        guards: HashSet::new(),
+        stack: vec![source_path],
+    };
+    let mut out = String::new();
+    expander.expand(&source, &dir, &mut out)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("atk-bundle-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn bundle_in(
+        dir: &Path,
+        library_paths: &[PathBuf],
+        acl_dir: Option<&Path>,
+    ) -> anyhow::Result<String> {
+        let options = BundleOptions {
+            library_paths,
+            acl_dir,
+        };
+        let result = bundle(&dir.join("main.cpp"), &options);
+        let _ = fs::remove_dir_all(dir);
+        result
+    }
+
+    #[test]
+    fn parses_include_lines() {
+        let cases = [
+            ("#include <vector>", Some(("vector", false))),
+            (
+                "  #  include \"lib/a.hpp\"  // comment",
+                Some(("lib/a.hpp", true)),
+            ),
+            ("#include<atcoder/all>", Some(("atcoder/all", false))),
+            ("#define include", None),
+            ("// #include <vector>", None),
+            ("#include MACRO", None),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(parse_include(line), expected, "{}", line);
+        }
+    }
+
+    #[test]
+    fn detects_include_guards() {
+        let cases = [
+            (
+                "#ifndef A_HPP\n#define A_HPP\nint a;\n#endif\n",
+                Some("A_HPP"),
+            ),
+            ("// comment\n\n#ifndef B\n#define B\n#endif\n", Some("B")),
+            ("#ifndef A\n#define B\n#endif\n", None),
+            ("#pragma once\nint a;\n", None),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(include_guard(source).as_deref(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn expands_guarded_headers_once() {
+        let dir = write_files(
+            "guarded",
+            &[
+                (
+                    "main.cpp",
+                    "#include \"a.hpp\"\n#include \"b.hpp\"\n#include <vector>\nint main() {}\n",
+                ),
+                ("a.hpp", "#pragma once\n#include \"b.hpp\"\nint a;\n"),
+                (
+                    "b.hpp",
+                    "#ifndef B_HPP\n#define B_HPP\n#include \"a.hpp\"\nint b;\n#endif\n",
+                ),
+            ],
+        );
+        let code = bundle_in(&dir, &[], None).unwrap();
+        assert_eq!(
+            code,
+            "#ifndef B_HPP\n#define B_HPP\nint b;\n#endif\nint a;\n#include <vector>\nint main() {}\n"
+        );
+    }
+
+    #[test]
+    fn searches_library_paths_and_acl() {
+        let dir = write_files(
+            "library",
+            &[
+                (
+                    "main.cpp",
+                    "#include \"my/lib.hpp\"\n#include <atcoder/dsu>\n",
+                ),
+                ("lib/my/lib.hpp", "#pragma once\nint lib;\n"),
+                (
+                    "acl/atcoder/dsu",
+                    "#ifndef ATCODER_DSU\n#define ATCODER_DSU\nint dsu;\n#endif\n",
+                ),
+            ],
+        );
+        let code = bundle_in(&dir, &[dir.join("lib")], Some(&dir.join("acl"))).unwrap();
+        assert_eq!(
+            code,
+            "int lib;\n#ifndef ATCODER_DSU\n#define ATCODER_DSU\nint dsu;\n#endif\n"
+        );
+    }
+
+    #[test]
+    fn keeps_unknown_headers() {
+        let dir = write_files(
+            "unknown",
+            &[(
+                "main.cpp",
+                "#include \"missing.hpp\"\n#include <atcoder/dsu>\n",
+            )],
+        );
+        let code = bundle_in(&dir, &[], None).unwrap();
+        assert_eq!(code, "#include \"missing.hpp\"\n#include <atcoder/dsu>\n");
+    }
+
+    #[test]
+    fn expands_unguarded_headers_every_time() {
+        let dir = write_files(
+            "unguarded",
+            &[
+                ("main.cpp", "#include \"x.inc\"\n#include \"x.inc\"\n"),
+                ("x.inc", "X\n"),
+            ],
+        );
+        assert_eq!(bundle_in(&dir, &[], None).unwrap(), "X\nX\n");
+    }
+
+    #[test]
+    fn rejects_include_cycles() {
+        let dir = write_files(
+            "cycle",
+            &[
+                ("main.cpp", "#include \"a.hpp\"\n"),
+                ("a.hpp", "#include \"b.hpp\"\n"),
+                ("b.hpp", "#include \"a.hpp\"\n"),
+            ],
+        );
+        let message = bundle_in(&dir, &[], None).unwrap_err().to_string();
+        assert!(message.contains("循環"), "{}", message);
+        assert!(
+            message.contains("a.hpp -> ") && message.ends_with("a.hpp"),
+            "{}",
+            message
+        );
+
+        let dir = write_files("self", &[("main.cpp", "#include \"main.cpp\"\n")]);
+        assert!(bundle_in(&dir, &[], None).is_err());
+    }
+
+    #[test]
+    fn reports_missing_acl_headers() {
+        let dir = write_files("acl", &[("main.cpp", "#include <atcoder/dsu>\n")]);
+        let acl_dir = dir.join("acl");
+        assert!(bundle_in(&dir, &[], Some(&acl_dir)).is_err());
+    }
+}
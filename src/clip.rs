@@ -0,0 +1,217 @@
+use crate::config::ClipboardBackend;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// ----------------------------------------------  //
+// クリップボードへのコピー
+// 環境に合わせて次の方法を順に試す
+// - wl-copy (Wayland)
+// - xclip, xsel (X11)
+// - clipboard クレート (X11 以外)
+// - OSC 52 (端末にエスケープシーケンスを送る。SSH 越しでも手元のクリップボードに届く)
+// 設定の clipboard.backend で使う方法を固定できる
+// ----------------------------------------------  //
+
+fn env_is_set(var: &str) -> bool {
+    std::env::var_os(var).is_some_and(|v| !v.is_empty())
+}
+
+fn candidates(wayland: bool, x11: bool, ssh: bool) -> Vec<ClipboardBackend> {
+    /*
+    環境から使えそうな方法を推定し、試す順に並べる
+    args:
+        wayland: WAYLAND_DISPLAY が設定されているか
+        x11: DISPLAY が設定されているか
+        ssh: SSH 越しに接続しているか
+    */
+    let mut backends = Vec::new();
+    // SSH 越しでディスプレイがない場合は、手元の端末に送るしかない
+    if ssh && !wayland && !x11 {
+        backends.push(ClipboardBackend::Osc52);
+    }
+    if wayland {
+        backends.push(ClipboardBackend::WlCopy);
+    }
+    if x11 {
+        backends.push(ClipboardBackend::Xclip);
+        backends.push(ClipboardBackend::Xsel);
+    }
+    // X11 では clipboard クレートが選択を持つプロセス (atk) の終了とともにコピーした内容が消えるのに
+    // 成功を返し、OSC 52 も試されなくなるため使わない
+    if !x11 {
+        backends.push(ClipboardBackend::Native);
+    }
+    if !backends.contains(&ClipboardBackend::Osc52) {
+        backends.push(ClipboardBackend::Osc52);
+    }
+    backends
+}
+
+fn backend_name(backend: ClipboardBackend) -> &'static str {
+    match backend {
+        ClipboardBackend::Auto => "auto",
+        ClipboardBackend::WlCopy => "wl-copy",
+        ClipboardBackend::Xclip => "xclip",
+        ClipboardBackend::Xsel => "xsel",
+        ClipboardBackend::Native => "clipboard クレート",
+        ClipboardBackend::Osc52 => "OSC 52",
+    }
+}
+
+fn copy_with_command(program: &str, args: &[&str], text: &str) -> anyhow::Result<()> {
+    // xclip や wl-copy は選択を保持するために常駐するプロセスを残し、それが出力を開いたままにするため、
+    // 出力をパイプにすると読み終わりを待ち続けてしまう
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("{}の起動に失敗: {}", program, e))?;
+    {
+        let stdin = child
+            .stdin
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("{}のstdin取得に失敗", program))?;
+        stdin.write_all(text.as_bytes())?;
+    } // 標準入力を閉じる
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("{} が失敗しました ({})", program, status));
+    }
+    Ok(())
+}
+
+fn copy_with_crate(text: &str) -> anyhow::Result<()> {
+    use clipboard::{ClipboardContext, ClipboardProvider};
+
+    let mut context: ClipboardContext =
+        ClipboardProvider::new().map_err(|e| anyhow::anyhow!("{}", e))?;
+    context
+        .set_contents(text.to_string())
+        .map_err(|e| anyhow::anyhow!("{}", e))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(TABLE[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn copy_with_osc52(text: &str) -> anyhow::Result<()> {
+    /*
+    OSC 52 のエスケープシーケンスを端末に送る
+    端末が対応していなくてもエラーにはならないため、成功したかどうかは確認できない
+    */
+    let mut sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    // tmux の中では外側の端末にそのまま渡すよう包む
+    if env_is_set("TMUX") {
+        sequence = format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"));
+    }
+    let mut tty = std::fs::OpenOptions::new()
+        .write(true)
+        .open("/dev/tty")
+        .map_err(|e| anyhow::anyhow!("端末を開けません: {}", e))?;
+    tty.write_all(sequence.as_bytes())?;
+    tty.flush()?;
+    Ok(())
+}
+
+fn copy_with(backend: ClipboardBackend, text: &str) -> anyhow::Result<()> {
+    match backend {
+        ClipboardBackend::WlCopy => copy_with_command("wl-copy", &[], text),
+        ClipboardBackend::Xclip => copy_with_command("xclip", &["-selection", "clipboard"], text),
+        ClipboardBackend::Xsel => copy_with_command("xsel", &["--clipboard", "--input"], text),
+        ClipboardBackend::Native => copy_with_crate(text),
+        ClipboardBackend::Osc52 => copy_with_osc52(text),
+        ClipboardBackend::Auto => unreachable!("auto は候補に展開してから呼ぶ"),
+    }
+}
+
+pub fn copy(text: &str, backend: ClipboardBackend) -> anyhow::Result<&'static str> {
+    /*
+    text をクリップボードにコピーする
+    args:
+        text: コピーする文字列
+        backend: 使う方法 (Auto の場合は環境から推定して順に試す)
+    returns:
+        実際に使った方法の名前
+    */
+    let backends = match backend {
+        ClipboardBackend::Auto => candidates(
+            env_is_set("WAYLAND_DISPLAY"),
+            env_is_set("DISPLAY"),
+            env_is_set("SSH_TTY") || env_is_set("SSH_CONNECTION"),
+        ),
+        backend => vec![backend],
+    };
+    let mut errors = Vec::new();
+    for backend in backends {
+        match copy_with(backend, text) {
+            Err(e) => errors.push(format!("  {}: {}", backend_name(backend), e)),
+            _ => return Ok(backend_name(backend)),
+        }
+    }
+    Err(anyhow::anyhow!(
+        "クリップボードにコピーできませんでした\n{}",
+        errors.join("\n")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_base64() {
+        let cases: [(&[u8], &str); 7] = [
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foobar", "Zm9vYmFy"),
+            (&[0xff, 0xfe, 0xfd], "//79"),
+            ("頂点".as_bytes(), "6aCC54K5"),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(base64(bytes), expected, "{:?}", bytes);
+        }
+    }
+
+    #[test]
+    fn orders_backends_by_environment() {
+        use ClipboardBackend::*;
+        let cases = [
+            // (wayland, x11, ssh)
+            ((false, false, false), vec![Native, Osc52]),
+            ((false, false, true), vec![Osc52, Native]),
+            ((true, false, false), vec![WlCopy, Native, Osc52]),
+            ((false, true, false), vec![Xclip, Xsel, Osc52]),
+            ((false, true, true), vec![Xclip, Xsel, Osc52]),
+            ((true, true, false), vec![WlCopy, Xclip, Xsel, Osc52]),
+        ];
+        for ((wayland, x11, ssh), expected) in cases {
+            assert_eq!(
+                candidates(wayland, x11, ssh),
+                expected,
+                "wayland: {}, x11: {}, ssh: {}",
+                wayland,
+                x11,
+                ssh
+            );
+        }
+    }
+}
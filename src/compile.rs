@@ -0,0 +1,359 @@
+use crate::bundle;
+use crate::config::{self, Config};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+// ----------------------------------------------  //
+// コンパイル結果のキャッシュ
+// ソースの内容 (-I で渡したディレクトリから #include で読み込むライブラリや ACL を含む)、
+// コンパイラのバージョン、フラグからハッシュを計算し、~/.cache/atk/build/<hash>.out に実行ファイルを保存する
+//
+// プリコンパイル済みヘッダ
+// ~/.cache/atk/pch/<hash>/iostream.gch のように置き、-I で渡すと
+// g++ が #include <iostream> の代わりに読み込む (ソースの最初の #include のみ)
+// <hash> はコンパイラのバージョン、フラグ、ヘッダの一覧から計算する
+// ----------------------------------------------  //
+
+// atk test --debug で加えるフラグ
+pub const DEBUG_FLAGS: &[&str] = &[
+    "-g",
+    "-fsanitize=address,undefined",
+    "-D_GLIBCXX_DEBUG",
+    "-DLOCAL",
+];
+
+// コンパイルに失敗したことを示すエラー (atk test の終了コードを分けるために使う)
+#[derive(Debug)]
+pub struct CompileError {
+    // コンパイラの標準エラー出力
+    pub message: String,
+}
+
+impl std::fmt::Display for CompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "コンパイルに失敗しました")
+    }
+}
+
+impl std::error::Error for CompileError {}
+
+// キャッシュのファイル名に使うハッシュ (FNV-1a)
+// std の DefaultHasher は Rust のバージョンによって値が変わりうるため、ディスクに残す名前には使わない
+struct KeyHasher(u64);
+
+impl KeyHasher {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn add(&mut self, bytes: &[u8]) {
+        // ["ab", "c"] と ["a", "bc"] を区別するため、長さも加える
+        for &b in (bytes.len() as u64).to_le_bytes().iter().chain(bytes) {
+            self.0 ^= u64::from(b);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn add_all(&mut self, items: &[String]) {
+        self.add(&(items.len() as u64).to_le_bytes());
+        for item in items {
+            self.add(item.as_bytes());
+        }
+    }
+
+    fn key(&self) -> String {
+        format!("{:016x}", self.0)
+    }
+}
+
+// 一定期間使われていないキャッシュは削除する
+const CACHE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+fn include_dirs(flags: &[String]) -> Vec<PathBuf> {
+    // -I で渡すディレクトリ (設定されたライブラリと ACL のパスを含む)
+    let mut dirs = Vec::new();
+    let mut args = flags.iter();
+    while let Some(flag) = args.next() {
+        match flag.strip_prefix("-I") {
+            Some("") => dirs.extend(args.next().map(PathBuf::from)),
+            Some(dir) => dirs.push(PathBuf::from(dir)),
+            None => {}
+        }
+    }
+    dirs
+}
+
+fn hash_sources(
+    path: &Path,
+    include_dirs: &[PathBuf],
+    hasher: &mut KeyHasher,
+    visited: &mut HashSet<PathBuf>,
+) {
+    /*
+    ソースと、そこから #include で読み込まれるファイルの内容をハッシュに加える
+    #include <...> は include_dirs にあるものだけを対象とし、システムのヘッダは
+    コンパイラのバージョンで区別する
+    */
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if !visited.insert(path.clone()) {
+        return;
+    }
+    let Ok(source) = fs::read_to_string(&path) else {
+        return;
+    };
+    hasher.add(source.as_bytes());
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    for line in source.lines() {
+        let Some((name, quoted)) = bundle::parse_include(line) else {
+            continue;
+        };
+        let header = if quoted {
+            bundle::resolve_quoted(name, dir, include_dirs)
+        } else {
+            include_dirs
+                .iter()
+                .map(|d| d.join(name))
+                .find(|p| p.is_file())
+        };
+        if let Some(header) = header {
+            hash_sources(&header, include_dirs, hasher, visited);
+        }
+    }
+}
+
+fn compiler_version(config: &Config) -> anyhow::Result<String> {
+    let compiler = &config.compile.compiler;
+    let output = Command::new(compiler)
+        .arg("--version")
+        .output()
+        .map_err(|e| anyhow::anyhow!("{}の起動に失敗: {}", compiler, e))?;
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn pch_root() -> anyhow::Result<PathBuf> {
+    Ok(config::cache_dir()?.join("pch"))
+}
+
+fn pch_dir(config: &Config, version: &str) -> anyhow::Result<PathBuf> {
+    let mut hasher = KeyHasher::new();
+    hasher.add(config.compile.compiler.as_bytes());
+    hasher.add(version.as_bytes());
+    hasher.add_all(&config.compile_flags()?);
+    hasher.add_all(&config.compile.pch_headers);
+    Ok(pch_root()?.join(hasher.key()))
+}
+
+pub fn build_pch(config: &Config) -> anyhow::Result<PathBuf> {
+    /*
+    設定されたヘッダを run_tests と同じコンパイラ・フラグでプリコンパイルする
+    古いコンパイラやフラグ向けのプリコンパイル済みヘッダは削除する
+    args:
+        config: atk の設定
+    returns:
+        プリコンパイル済みヘッダを置いたディレクトリ (-I で渡す)
+    */
+    let compiler = &config.compile.compiler;
+    let flags = config.compile_flags()?;
+    if config.compile.pch_headers.is_empty() {
+        return Err(anyhow::anyhow!(
+            "プリコンパイルするヘッダが設定されていません (compile.pch_headers)"
+        ));
+    }
+    let version = compiler_version(config)?;
+    let dir = pch_dir(config, &version)?;
+    let root = pch_root()?;
+
+    if let Ok(entries) = fs::read_dir(&root) {
+        for entry in entries.flatten() {
+            if entry.path() != dir {
+                let _ = fs::remove_dir_all(entry.path());
+            }
+        }
+    }
+    let _ = fs::remove_dir_all(&dir);
+
+    for header in &config.compile.pch_headers {
+        eprintln!("{} をプリコンパイルしています...", header);
+        let gch = dir.join(format!("{}.gch", header));
+        if let Some(parent) = gch.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // ヘッダ本体の場所を探さずに済むよう、それを読み込むだけのヘッダをプリコンパイルする
+        let wrapper = dir.join(format!(".{}.h", header.replace('/', "_")));
+        fs::write(&wrapper, format!("#include <{}>\n", header))?;
+
+        let output = Command::new(compiler)
+            .args(&flags)
+            .arg("-x")
+            .arg("c++-header")
+            .arg(&wrapper)
+            .arg("-o")
+            .arg(&gch)
+            .output()
+            .map_err(|e| anyhow::anyhow!("{}の起動に失敗: {}", compiler, e))?;
+        fs::remove_file(&wrapper)?;
+        if !output.status.success() {
+            let _ = fs::remove_dir_all(&dir);
+            eprintln!("{}", String::from_utf8_lossy(&output.stderr));
+            return Err(anyhow::anyhow!("{} のプリコンパイルに失敗しました", header));
+        }
+    }
+    // 全てのヘッダのプリコンパイルが終わったことを示す
+    fs::write(dir.join(".complete"), "")?;
+    Ok(dir)
+}
+
+pub fn clean_pch() -> anyhow::Result<()> {
+    let root = pch_root()?;
+    if root.exists() {
+        fs::remove_dir_all(&root)?;
+    }
+    Ok(())
+}
+
+fn pch_include_dir(config: &Config, version: &str) -> anyhow::Result<Option<PathBuf>> {
+    // atk pch build を一度も実行していなければ使わない
+    let root = pch_root()?;
+    if config.compile.pch_headers.is_empty() || !root.exists() {
+        return Ok(None);
+    }
+    let dir = pch_dir(config, version)?;
+    if dir.join(".complete").exists() {
+        return Ok(Some(dir));
+    }
+    // コンパイラのバージョンかフラグが変わったので作り直す
+    eprintln!("コンパイラの設定が変わったため、プリコンパイル済みヘッダを作り直します");
+    Ok(Some(build_pch(config)?))
+}
+
+fn prune_cache(build_dir: &Path) {
+    let Ok(entries) = fs::read_dir(build_dir) else {
+        return;
+    };
+    let now = SystemTime::now();
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| now.duration_since(modified).unwrap_or_default() > CACHE_TTL);
+        if expired {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+pub fn compile_cpp(source: &Path, config: &Config, debug: bool) -> anyhow::Result<PathBuf> {
+    /*
+    ソースファイルをコンパイルし、実行ファイルのパスを返す
+    ソースとコンパイラが前回から変わっていなければキャッシュ済みの実行ファイルを使う
+    args:
+        source: ソースファイルのパス
+        config: atk の設定
+        debug: サニタイザとデバッグ用のマクロを有効にするかどうか
+    returns:
+        実行ファイルのパス
+    */
+    let compiler = &config.compile.compiler;
+    let mut flags = config.compile_flags()?;
+    if debug {
+        flags.extend(DEBUG_FLAGS.iter().map(|f| f.to_string()));
+    }
+    let version = compiler_version(config)?;
+
+    let mut hasher = KeyHasher::new();
+    hash_sources(
+        source,
+        &include_dirs(&flags),
+        &mut hasher,
+        &mut HashSet::new(),
+    );
+    hasher.add(compiler.as_bytes());
+    hasher.add(version.as_bytes());
+    hasher.add_all(&flags);
+    let key = hasher.key();
+
+    let build_dir = config::cache_dir()?.join("build");
+    fs::create_dir_all(&build_dir)?;
+    let executable = build_dir.join(format!("{}.out", key));
+
+    if executable.exists() {
+        // 最終利用日時を更新して削除対象から外す
+        if let Ok(file) = fs::File::options().append(true).open(&executable) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        return Ok(executable);
+    }
+
+    // 同時に実行された atk と衝突しないよう、一時ファイルに出力してから置き換える
+    let tmp = build_dir.join(format!("{}.{}.tmp", key, std::process::id()));
+    let mut command = Command::new(compiler);
+    // プリコンパイル済みヘッダはフラグが異なると使えないため、デバッグビルドでは使わない
+    if !debug && let Some(pch) = pch_include_dir(config, &version)? {
+        command.arg("-I").arg(pch);
+    }
+    let compile_output = command
+        .arg(source)
+        .args(&flags)
+        .arg("-o")
+        .arg(&tmp)
+        .output()
+        .map_err(|e| anyhow::anyhow!("{}の起動に失敗: {}", compiler, e))?;
+
+    if !compile_output.status.success() {
+        let _ = fs::remove_file(&tmp);
+        let message = String::from_utf8_lossy(&compile_output.stderr).to_string();
+        eprintln!("コンパイルエラー:\n{}", message);
+        return Err(CompileError { message }.into());
+    }
+    fs::rename(&tmp, &executable)?;
+
+    prune_cache(&build_dir);
+    Ok(executable)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(items: &[&str]) -> String {
+        let mut hasher = KeyHasher::new();
+        for item in items {
+            hasher.add(item.as_bytes());
+        }
+        hasher.key()
+    }
+
+    #[test]
+    fn hashes_keys_stably() {
+        // 値が変わるとキャッシュが全て作り直しになるため、固定の値と比べる
+        assert_eq!(key(&[]), "cbf29ce484222325");
+        assert_eq!(key(&[""]), "a8c7f832281a39c5");
+        assert_eq!(key(&["ab", "c"]), key(&["ab", "c"]));
+        assert_ne!(key(&["ab", "c"]), key(&["a", "bc"]));
+
+        let (mut a, mut b) = (KeyHasher::new(), KeyHasher::new());
+        a.add_all(&["-O2".to_string()]);
+        b.add_all(&[]);
+        b.add(b"-O2");
+        assert_ne!(a.key(), b.key());
+    }
+
+    #[test]
+    fn finds_include_dirs() {
+        let flags: Vec<String> = ["-O2", "-I/lib", "-I", "/acl", "-Iinclude", "-DLOCAL"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(
+            include_dirs(&flags),
+            [
+                PathBuf::from("/lib"),
+                PathBuf::from("/acl"),
+                PathBuf::from("include")
+            ]
+        );
+    }
+}
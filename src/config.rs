@@ -0,0 +1,204 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// ----------------------------------------------  //
+// 設定ファイル ($XDG_CONFIG_HOME/atk/config.toml もしくは ~/.config/atk/config.toml)
+//
+//   [compile]
+//   compiler = "g++"
+//   flags = ["-std=gnu++20", "-O2"]
+//   pch_headers = ["iostream"]     (atk pch build でプリコンパイルするヘッダ)
+//
+//   [acl]
+//   path = "/path/to/ac-library"   (省略時は atk acl install の展開先)
+//
+//   [library]
+//   paths = ["~/lib"]              (#include "..." を探すディレクトリ)
+//
+//   [clipboard]
+//   backend = "auto"               (auto, wl-copy, xclip, xsel, native, osc52)
+//
+//   [strip]                        (atk copy で提出用のコードから取り除くデバッグ用のコード)
+//   enabled = true
+//   ifdef = ["LOCAL"]              (#ifdef LOCAL ... #endif を取り除く)
+//   markers = [{ begin = "// atk:debug-begin", end = "// atk:debug-end" }]
+//   macros = ["dbg"]               (dbg(...); の呼び出しを取り除く)
+//
+// 設定ファイルが存在しない場合や項目が省略された場合は既定値を使う
+// ----------------------------------------------  //
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub compile: CompileConfig,
+    pub acl: AclConfig,
+    pub library: LibraryConfig,
+    pub clipboard: ClipboardConfig,
+    pub strip: StripConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompileConfig {
+    pub compiler: String,
+    pub flags: Vec<String>,
+    // atk pch build でプリコンパイルするヘッダ
+    pub pch_headers: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AclConfig {
+    // atcoder/ ディレクトリを含むディレクトリ
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LibraryConfig {
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClipboardConfig {
+    pub backend: ClipboardBackend,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClipboardBackend {
+    // 環境から推定し、使えるものを順に試す
+    #[default]
+    Auto,
+    WlCopy,
+    Xclip,
+    Xsel,
+    // clipboard クレート
+    Native,
+    // 端末のエスケープシーケンス (SSH 越しでも使える)
+    Osc52,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StripConfig {
+    pub enabled: bool,
+    // #ifdef/#ifndef で分岐を取り除くマクロ (ジャッジでは定義されていないもの)
+    pub ifdef: Vec<String>,
+    // begin の行から end の行までを取り除く
+    pub markers: Vec<StripMarker>,
+    // 文として書かれた呼び出しを取り除くマクロ
+    pub macros: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StripMarker {
+    pub begin: String,
+    pub end: String,
+}
+
+impl Default for CompileConfig {
+    fn default() -> Self {
+        Self {
+            compiler: "g++".to_string(),
+            flags: Vec::new(),
+            // テンプレートの最初の #include に合わせる
+            pch_headers: vec!["iostream".to_string()],
+        }
+    }
+}
+
+impl Default for StripConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ifdef: vec!["LOCAL".to_string()],
+            markers: vec![StripMarker {
+                begin: "// atk:debug-begin".to_string(),
+                end: "// atk:debug-end".to_string(),
+            }],
+            macros: vec!["dbg".to_string()],
+        }
+    }
+}
+
+impl Config {
+    pub fn acl_dir(&self) -> anyhow::Result<PathBuf> {
+        match &self.acl.path {
+            Some(path) => Ok(path.clone()),
+            None => Ok(data_dir()?.join("ac-library")),
+        }
+    }
+
+    pub fn library_paths(&self) -> anyhow::Result<Vec<PathBuf>> {
+        // 先頭の ~ はホームディレクトリに置き換える
+        self.library
+            .paths
+            .iter()
+            .map(|path| match path.strip_prefix("~") {
+                std::result::Result::Ok(rest) => Ok(home_dir()?.join(rest)),
+                Err(_) => Ok(path.clone()),
+            })
+            .collect()
+    }
+
+    pub fn compile_flags(&self) -> anyhow::Result<Vec<String>> {
+        /*
+        コンパイル時に渡すフラグ (設定されたフラグにライブラリと ACL のインクルードパスを加えたもの)
+        */
+        let mut flags = self.compile.flags.clone();
+        for path in self.library_paths()? {
+            flags.push(format!("-I{}", path.display()));
+        }
+        let acl_dir = self.acl_dir()?;
+        if acl_dir.join("atcoder").is_dir() {
+            flags.push(format!("-I{}", acl_dir.display()));
+        }
+        Ok(flags)
+    }
+}
+
+fn home_dir() -> anyhow::Result<PathBuf> {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or_else(|| anyhow::anyhow!("HOME が設定されていません"))
+}
+
+fn xdg_dir(var: &str, default: &Path) -> anyhow::Result<PathBuf> {
+    match std::env::var_os(var).filter(|d| !d.is_empty()) {
+        Some(dir) => Ok(PathBuf::from(dir).join("atk")),
+        None => Ok(home_dir()?.join(default).join("atk")),
+    }
+}
+
+pub fn data_dir() -> anyhow::Result<PathBuf> {
+    // $XDG_DATA_HOME/atk もしくは ~/.local/share/atk
+    xdg_dir("XDG_DATA_HOME", Path::new(".local/share"))
+}
+
+pub fn cache_dir() -> anyhow::Result<PathBuf> {
+    // $XDG_CACHE_HOME/atk もしくは ~/.cache/atk
+    xdg_dir("XDG_CACHE_HOME", Path::new(".cache"))
+}
+
+pub fn config_path() -> anyhow::Result<PathBuf> {
+    Ok(xdg_dir("XDG_CONFIG_HOME", Path::new(".config"))?.join("config.toml"))
+}
+
+pub fn load() -> anyhow::Result<Config> {
+    /*
+    設定ファイルを読み込む
+    returns:
+        設定 (ファイルが存在しない場合は既定値)
+    */
+    let path = config_path()?;
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+    let text = fs::read_to_string(&path)?;
+    toml::from_str(&text)
+        .map_err(|e| anyhow::anyhow!("設定ファイル {} の読み込みに失敗: {}", path.display(), e))
+}
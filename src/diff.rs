@@ -0,0 +1,342 @@
+use colored::{Color, Colorize};
+
+// ----------------------------------------------  //
+// WA の際の出力の比較
+// 期待する出力と実際の出力を行ごとに並べ、最初に食い違った行とトークンを強調する
+// 出力が長い場合は最初の食い違いの周辺だけを表示する
+// ----------------------------------------------  //
+
+// 最初の食い違いより前に表示する行数
+const CONTEXT_LINES: usize = 3;
+// 省略せずに表示する最大の行数
+const MAX_LINES: usize = 20;
+// 1行に表示する最大の文字数
+const MAX_LINE_WIDTH: usize = 200;
+// 長い行で、食い違ったトークンより前に表示するトークン数
+const CONTEXT_TOKENS: usize = 5;
+
+#[derive(Debug, PartialEq)]
+pub struct Mismatch {
+    // 0-indexed の行番号
+    pub line: usize,
+    // 0-indexed のトークン番号 (行が片方にしかない場合は None)
+    // 片方のトークンが足りない場合は、足りない最初のトークンの番号
+    pub token: Option<usize>,
+    // トークンは全て一致し、token 番目のトークンの前の空白だけが異なる
+    pub whitespace: bool,
+}
+
+fn token_spans(line: &str) -> Vec<(usize, usize)> {
+    // 空白で区切られたトークンのバイト位置 [start, end)
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (i, c) in line.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                spans.push((s, i));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        spans.push((s, line.len()));
+    }
+    spans
+}
+
+fn separator_span(spans: &[(usize, usize)], token: usize, len: usize) -> (usize, usize) {
+    // token 番目のトークンの前の空白のバイト位置 (token が末尾の場合は行末の空白)
+    let start = match token {
+        0 => 0,
+        _ => spans[token - 1].1,
+    };
+    let end = spans.get(token).map_or(len, |span| span.0);
+    (start, end)
+}
+
+impl Mismatch {
+    pub fn range_in(&self, line: &str) -> Option<(usize, usize)> {
+        /*
+        食い違った部分の line でのバイト位置を返す
+        returns:
+            強調する範囲 (line にトークンがない場合は None)
+        */
+        let token = self.token?;
+        let spans = token_spans(line);
+        if self.whitespace {
+            return (token <= spans.len()).then(|| separator_span(&spans, token, line.len()));
+        }
+        spans.get(token).copied()
+    }
+}
+
+pub fn first_mismatch(expected: &[&str], actual: &[&str]) -> Option<Mismatch> {
+    /*
+    最初に食い違う行と、その行で最初に食い違うトークンを探す
+    args:
+        expected: 期待する出力の各行
+        actual: 実際の出力の各行
+    returns:
+        最初の食い違いの位置 (全て一致する場合は None)
+    */
+    let line = (0..expected.len().max(actual.len())).find(|&i| expected.get(i) != actual.get(i))?;
+    let (Some(e), Some(a)) = (expected.get(line), actual.get(line)) else {
+        return Some(Mismatch {
+            line,
+            token: None,
+            whitespace: false,
+        });
+    };
+    let e_spans = token_spans(e);
+    let a_spans = token_spans(a);
+    let e_tokens: Vec<&str> = e_spans.iter().map(|&(s, t)| &e[s..t]).collect();
+    let a_tokens: Vec<&str> = a_spans.iter().map(|&(s, t)| &a[s..t]).collect();
+    if let Some(token) =
+        (0..e_tokens.len().max(a_tokens.len())).find(|&i| e_tokens.get(i) != a_tokens.get(i))
+    {
+        return Some(Mismatch {
+            line,
+            token: Some(token),
+            whitespace: false,
+        });
+    }
+    // トークンが全て一致するので、どこかの空白が異なる
+    let token = (0..=e_spans.len())
+        .find(|&i| {
+            let (es, ee) = separator_span(&e_spans, i, e.len());
+            let (as_, ae) = separator_span(&a_spans, i, a.len());
+            e[es..ee] != a[as_..ae]
+        })
+        .unwrap_or(e_spans.len());
+    Some(Mismatch {
+        line,
+        token: Some(token),
+        whitespace: true,
+    })
+}
+
+fn truncate(line: &str) -> String {
+    if line.chars().count() <= MAX_LINE_WIDTH {
+        return line.to_string();
+    }
+    let head: String = line.chars().take(MAX_LINE_WIDTH).collect();
+    format!("{}...", head)
+}
+
+fn visible_window(line: &str, start: usize, end: usize) -> (usize, usize) {
+    /*
+    長い行で表示する範囲のバイト位置を返す
+    強調する範囲 [start, end) の CONTEXT_TOKENS 個前のトークンから、MAX_LINE_WIDTH 文字に収まるまで
+    */
+    if line.chars().count() <= MAX_LINE_WIDTH {
+        return (0, line.len());
+    }
+    let spans = token_spans(line);
+    let before = spans.iter().filter(|span| span.0 < start).count();
+    let from = spans
+        .get(before.saturating_sub(CONTEXT_TOKENS))
+        .map_or(start, |span| span.0.min(start));
+    let to = spans
+        .iter()
+        .map(|span| span.1)
+        .rfind(|&e| e > end && line[from..e].chars().count() <= MAX_LINE_WIDTH)
+        .unwrap_or(end);
+    (from, to)
+}
+
+fn highlight_range(line: &str, range: Option<(usize, usize)>, color: Color) -> String {
+    // 行を color で色付けし、range (バイト位置) の部分を反転表示する
+    let Some((start, end)) = range else {
+        return truncate(line).color(color).to_string();
+    };
+    let (from, to) = visible_window(line, start, end);
+    format!(
+        "{}{}{}{}{}",
+        if from > 0 { "... " } else { "" }.color(color),
+        line[from..start].color(color),
+        line[start..end].color(color).reversed(),
+        line[end..to].color(color),
+        if to < line.len() { " ..." } else { "" }.color(color)
+    )
+}
+
+pub fn print_diff(expected: &str, actual: &str, show_all: bool) {
+    /*
+    期待する出力と実際の出力の差分を表示する
+    args:
+        expected: 期待する出力 (整形済み)
+        actual: 実際の出力 (整形済み)
+        show_all: 省略せずに全ての行を表示するかどうか
+    */
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    let Some(mismatch) = first_mismatch(&expected_lines, &actual_lines) else {
+        return;
+    };
+
+    let token_count = |lines: &[&str]| lines.get(mismatch.line).map_or(0, |l| token_spans(l).len());
+    match mismatch.token {
+        Some(token) if mismatch.whitespace => println!(
+            "{} 行目の {} 番目のトークンの前の空白が異なります",
+            mismatch.line + 1,
+            token + 1
+        ),
+        Some(token) if token >= token_count(&actual_lines) => println!(
+            "{} 行目の {} 番目のトークンがありません",
+            mismatch.line + 1,
+            token + 1
+        ),
+        Some(token) if token >= token_count(&expected_lines) => println!(
+            "{} 行目の {} 番目以降のトークンが余分です",
+            mismatch.line + 1,
+            token + 1
+        ),
+        Some(token) => println!(
+            "{} 行目の {} 番目のトークンが異なります",
+            mismatch.line + 1,
+            token + 1
+        ),
+        None if expected_lines.len() > actual_lines.len() => println!(
+            "出力の行数が足りません (期待: {} 行, 実際: {} 行)",
+            expected_lines.len(),
+            actual_lines.len()
+        ),
+        None => println!(
+            "出力の行数が多すぎます (期待: {} 行, 実際: {} 行)",
+            expected_lines.len(),
+            actual_lines.len()
+        ),
+    }
+
+    let total = expected_lines.len().max(actual_lines.len());
+    let (start, end) = if show_all {
+        (0, total)
+    } else {
+        let start = mismatch.line.saturating_sub(CONTEXT_LINES);
+        (start, (start + MAX_LINES).min(total))
+    };
+    let width = total.to_string().len();
+
+    if start > 0 {
+        println!("{}", format!("  ... ({} 行省略)", start).dimmed());
+    }
+    for i in start..end {
+        let (e, a) = (expected_lines.get(i), actual_lines.get(i));
+        if e == a {
+            let line = truncate(e.copied().unwrap_or_default());
+            println!("  {:>width$} | {}", i + 1, line.dimmed(), width = width);
+            continue;
+        }
+        let range = |line: &str| {
+            if i == mismatch.line {
+                mismatch.range_in(line)
+            } else {
+                None
+            }
+        };
+        if let Some(e) = e {
+            println!(
+                "{} {:>width$} | {}",
+                "-".green(),
+                i + 1,
+                highlight_range(e, range(e), Color::Green),
+                width = width
+            );
+        }
+        if let Some(a) = a {
+            println!(
+                "{} {:>width$} | {}",
+                "+".red(),
+                i + 1,
+                highlight_range(a, range(a), Color::Red),
+                width = width
+            );
+        }
+    }
+    if end < total {
+        println!(
+            "{}",
+            format!("  ... ({} 行省略, --full-diff で全て表示)", total - end).dimmed()
+        );
+    }
+    println!(
+        "{}",
+        format!(
+            "  ({} 期待する出力 / {} 実際の出力)",
+            "-".green(),
+            "+".red()
+        )
+        .dimmed()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mismatch(expected: &str, actual: &str) -> Option<Mismatch> {
+        let expected: Vec<&str> = expected.lines().collect();
+        let actual: Vec<&str> = actual.lines().collect();
+        first_mismatch(&expected, &actual)
+    }
+
+    #[test]
+    fn finds_first_mismatch() {
+        let cases = [
+            ("1 2 3", "1 2 3", None),
+            ("1\n2\n3", "1\n2\n4", Some((2, Some(0), false))),
+            ("1 2 3", "1 5 3", Some((0, Some(1), false))),
+            // 足りないトークン、余分なトークン
+            ("1 2 3", "1 2", Some((0, Some(2), false))),
+            ("1 2", "1 2 3 4", Some((0, Some(2), false))),
+            ("", "1", Some((0, None, false))),
+            // 行が片方にしかない
+            ("1\n2", "1", Some((1, None, false))),
+            ("1", "1\n2", Some((1, None, false))),
+            // 空白だけが異なる
+            ("1 2 3", "1 2  3", Some((0, Some(2), true))),
+            ("1 2", " 1 2", Some((0, Some(0), true))),
+            ("1 2", "1\t2", Some((0, Some(1), true))),
+            ("あ い う", "あ い え", Some((0, Some(2), false))),
+        ];
+        for (expected, actual, result) in cases {
+            let found = mismatch(expected, actual).map(|m| (m.line, m.token, m.whitespace));
+            assert_eq!(found, result, "{:?} / {:?}", expected, actual);
+        }
+    }
+
+    #[test]
+    fn locates_mismatch_by_byte_offsets() {
+        let cases = [
+            ("1 2 3", "1 5 3", Some((2, 3)), Some((2, 3))),
+            ("αβ γδ ε", "αβ γδ ζ", Some((10, 12)), Some((10, 12))),
+            ("あい うえ", "あい  うえ", Some((6, 7)), Some((6, 8))),
+            ("1 2 3", "1 2", Some((4, 5)), None),
+        ];
+        for (expected, actual, e_range, a_range) in cases {
+            let m = mismatch(expected, actual).unwrap();
+            assert_eq!(m.range_in(expected), e_range, "{}", expected);
+            assert_eq!(m.range_in(actual), a_range, "{}", actual);
+        }
+    }
+
+    #[test]
+    fn shows_window_around_mismatch_in_long_lines() {
+        let line = (0..200)
+            .map(|i| format!("値{}", i))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let spans = token_spans(&line);
+        let (start, end) = spans[150];
+        let (from, to) = visible_window(&line, start, end);
+        assert_eq!(from, spans[150 - CONTEXT_TOKENS].0);
+        assert!(to >= end && to < line.len());
+        assert!(line[from..to].chars().count() <= MAX_LINE_WIDTH);
+        assert!(line.is_char_boundary(from) && line.is_char_boundary(to));
+
+        let short = "短い 行";
+        assert_eq!(visible_window(short, 0, 6), (0, short.len()));
+    }
+}
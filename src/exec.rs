@@ -0,0 +1,346 @@
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+// ----------------------------------------------  //
+// プログラムの実行
+// 標準入力を与えて実行し、出力、実行時間、最大使用メモリを測る
+// 実行時間制限を超えたプロセスは止め、終了理由を人間向けの文字列にする
+// atk test, atk run, atk stress, atk ahc run で共通に使う
+// ----------------------------------------------  //
+
+// 子プロセスの終了と最大使用メモリを確かめる間隔の上限
+// 並列に実行している他のケースの CPU 時間を奪わないよう、最初の数 ms を過ぎたらこの間隔にする
+const MAX_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
+pub struct Execution {
+    pub output: std::process::Output,
+    pub elapsed: std::time::Duration,
+    // 最大使用メモリ (KB)
+    pub memory: Option<u64>,
+    // 実行時間制限を超えたため止めた
+    pub timed_out: bool,
+}
+
+pub fn program_command(executable: &Path) -> Command {
+    let mut command = Command::new(executable);
+    // 競プロでは解放しないメモリがよくあるため、ASan のリーク検出は既定で無効にする
+    if env::var_os("ASAN_OPTIONS").is_none() {
+        command.env("ASAN_OPTIONS", "detect_leaks=0");
+    }
+    command
+}
+
+pub fn run_executable(
+    executable: &Path,
+    input: &str,
+    time_limit: Option<std::time::Duration>,
+) -> anyhow::Result<Execution> {
+    /*
+    実行ファイルに input を標準入力として与えて実行し、出力と実行時間を返す
+    time_limit を超えた場合はプロセスを止める
+    */
+    run_command(program_command(executable), input, time_limit)
+}
+
+pub fn run_command(
+    mut command: Command,
+    input: &str,
+    time_limit: Option<std::time::Duration>,
+) -> anyhow::Result<Execution> {
+    /*
+    command に input を標準入力として与えて実行し、出力と実行時間を返す
+    time_limit を超えた場合はプロセスを止める
+    */
+    let start = std::time::Instant::now();
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // 入出力でプロセスと待ち合わせないよう、別スレッドで読み書きする
+    let mut child_stdin = child.stdin.take().unwrap();
+    let input = input.to_string();
+    let writer = std::thread::spawn(move || {
+        let _ = child_stdin.write_all(input.as_bytes());
+    }); // 書き終えたら標準入力を閉じる
+    let read_all = |mut pipe: Box<dyn Read + Send>| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = pipe.read_to_end(&mut buf);
+            buf
+        })
+    };
+    let stdout = read_all(Box::new(child.stdout.take().unwrap()));
+    let stderr = read_all(Box::new(child.stderr.take().unwrap()));
+
+    let (status, memory, timed_out) = wait_with_limit(&mut child, start, time_limit)?;
+    let elapsed = start.elapsed();
+
+    let _ = writer.join();
+    let output = std::process::Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    };
+    Ok(Execution {
+        output,
+        elapsed,
+        memory,
+        timed_out,
+    })
+}
+
+pub fn wait_with_limit(
+    child: &mut std::process::Child,
+    start: std::time::Instant,
+    time_limit: Option<std::time::Duration>,
+) -> anyhow::Result<(std::process::ExitStatus, Option<u64>, bool)> {
+    /*
+    子プロセスの終了を待ち、time_limit を超えた場合は止める
+    args:
+        child: 子プロセス
+        start: 実行を開始した時刻
+        time_limit: 実行時間制限
+    returns:
+        (終了状態, 最大使用メモリ (KB), 実行時間制限を超えたため止めたかどうか)
+    */
+    // 終了後は読めないため、実行中に定期的に最大使用メモリを読んでおく
+    let mut memory = peak_memory(child.id());
+    // すぐに終わるプログラムの実行時間を長く見積もらないよう、間隔は 1ms から倍々に延ばす
+    let mut interval = std::time::Duration::from_millis(1);
+    loop {
+        if let Some(peak) = peak_memory(child.id()) {
+            memory = memory.max(Some(peak));
+        }
+        if let Some(status) = child.try_wait()? {
+            return Ok((status, memory, false));
+        }
+        if let Some(limit) = time_limit
+            && start.elapsed() > limit
+        {
+            kill_process(child);
+            return Ok((child.wait()?, memory, true));
+        }
+        // 実行時間制限の直後に確かめられるよう、制限までの残り時間より長くは待たない
+        let remaining = time_limit.map(|limit| limit.saturating_sub(start.elapsed()));
+        let sleep = remaining.map_or(interval, |remaining| {
+            interval.min(remaining + std::time::Duration::from_millis(1))
+        });
+        std::thread::sleep(sleep);
+        interval = (interval * 2).min(MAX_POLL_INTERVAL);
+    }
+}
+
+fn kill_process(child: &mut std::process::Child) {
+    /*
+    子プロセスを止める
+    子プロセスが自身のプロセスグループを持つ場合 (AHC の tester を process_group(0) で起動した場合) は、
+    グループ全体を止めて孫プロセスが出力のパイプを開いたまま残らないようにする
+    */
+    // 子プロセスは回収前なので、その pid と同じ番号のプロセスグループは子プロセスが作ったものに限られる
+    // (グループを持たない場合は ESRCH で失敗するだけ)
+    #[cfg(unix)]
+    unsafe {
+        libc::killpg(child.id() as libc::pid_t, libc::SIGKILL);
+    }
+    let _ = child.kill();
+}
+
+fn peak_memory(pid: u32) -> Option<u64> {
+    /*
+    /proc/<pid>/status の VmHWM から、プロセスのこれまでの最大使用メモリ (KB) を読む
+    wait4 の ru_maxrss は fork 元の atk のメモリも含んでしまうため使わない
+    */
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(unix)]
+fn signal_name(signal: i32) -> Option<&'static str> {
+    // シグナルの番号は OS によって異なる (SIGBUS は Linux では 7、macOS では 10)
+    match signal {
+        libc::SIGHUP => Some("SIGHUP"),
+        libc::SIGINT => Some("SIGINT"),
+        libc::SIGILL => Some("SIGILL"),
+        libc::SIGTRAP => Some("SIGTRAP"),
+        libc::SIGABRT => Some("SIGABRT"),
+        libc::SIGBUS => Some("SIGBUS"),
+        libc::SIGFPE => Some("SIGFPE"),
+        libc::SIGKILL => Some("SIGKILL"),
+        libc::SIGSEGV => Some("SIGSEGV"),
+        libc::SIGPIPE => Some("SIGPIPE"),
+        libc::SIGALRM => Some("SIGALRM"),
+        libc::SIGTERM => Some("SIGTERM"),
+        libc::SIGXCPU => Some("SIGXCPU"),
+        libc::SIGXFSZ => Some("SIGXFSZ"),
+        _ => None,
+    }
+}
+
+#[cfg(unix)]
+pub fn exit_description(status: &std::process::ExitStatus) -> String {
+    /*
+    プロセスの終了理由を説明する文字列を返す
+    returns:
+        "SIGSEGV (signal 11)" や "exit code 1" など
+    */
+    use std::os::unix::process::ExitStatusExt;
+
+    if let Some(signal) = status.signal() {
+        let hint = match signal {
+            libc::SIGSEGV => " 範囲外アクセスやスタックオーバーフローの可能性があります",
+            libc::SIGFPE => " ゼロ除算の可能性があります",
+            libc::SIGABRT => " assert の失敗や例外の可能性があります",
+            _ => "",
+        };
+        return match signal_name(signal) {
+            Some(name) => format!("{} (signal {}){}", name, signal, hint),
+            None => format!("signal {}", signal),
+        };
+    }
+    match status.code() {
+        Some(code) => format!("exit code {}", code),
+        None => "unknown".to_string(),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn exit_description(status: &std::process::ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("exit code {}", code),
+        None => "unknown".to_string(),
+    }
+}
+
+pub fn run_parallel<T: Send>(
+    count: usize,
+    jobs: usize,
+    task: impl Fn(usize) -> T + Sync,
+    mut on_result: impl FnMut(usize, T) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    /*
+    task(0), ..., task(count - 1) を最大 jobs 個並列に実行し、結果を番号順に on_result へ渡す
+    on_result がエラーを返した場合は残りの結果を捨てる
+    */
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let next_task = AtomicUsize::new(0);
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, count.max(1)) {
+            let tx = tx.clone();
+            let next_task = &next_task;
+            let task = &task;
+            scope.spawn(move || {
+                loop {
+                    let i = next_task.fetch_add(1, Ordering::Relaxed);
+                    if i >= count || tx.send((i, task(i))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        // 終わった順に届く結果を並べ替え、先頭から揃った分だけ渡す
+        let mut pending = std::collections::BTreeMap::new();
+        let mut next_result = 0;
+        for (i, result) in rx {
+            pending.insert(i, result);
+            while let Some(result) = pending.remove(&next_result) {
+                on_result(next_result, result)?;
+                next_result += 1;
+            }
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_parallel_results_in_order() {
+        let mut results = Vec::new();
+        run_parallel(
+            20,
+            4,
+            |i| {
+                // 後の番号ほど先に終わるようにする
+                std::thread::sleep(std::time::Duration::from_millis(20 - i as u64));
+                i * i
+            },
+            |i, result| {
+                results.push((i, result));
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(results, (0..20).map(|i| (i, i * i)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn stops_at_the_first_error() {
+        let mut seen = Vec::new();
+        let result = run_parallel(
+            10,
+            2,
+            |i| i,
+            |i, _| {
+                seen.push(i);
+                if i == 3 {
+                    return Err(anyhow::anyhow!("stop"));
+                }
+                Ok(())
+            },
+        );
+        assert!(result.is_err());
+        assert_eq!(seen, vec![0, 1, 2, 3]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stops_programs_over_the_time_limit() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "cat; exec sleep 5"]);
+        let execution = run_command(
+            command,
+            "hello",
+            Some(std::time::Duration::from_millis(100)),
+        )
+        .unwrap();
+        assert!(execution.timed_out);
+        assert_eq!(execution.output.stdout, b"hello");
+        assert_eq!(
+            exit_description(&execution.output.status),
+            "SIGKILL (signal 9)"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stops_process_groups_over_the_time_limit() {
+        // 孫プロセスが標準出力を開いたままでも、グループごと止めて待たずに戻る
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 5 & wait"]);
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let start = std::time::Instant::now();
+        let execution =
+            run_command(command, "", Some(std::time::Duration::from_millis(100))).unwrap();
+        assert!(execution.timed_out);
+        assert!(start.elapsed() < std::time::Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,679 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+use std::collections::{HashMap, HashSet};
+
+// ----------------------------------------------  //
+// 入力生成用の仕様ファイル
+//
+// 1行(もしくは ; 区切り)が出力の1行に対応する
+// 同じ行に複数の値を並べる場合は , で区切る
+//
+//   N: int[1,10], M: int[0,N*(N-1)/2]
+//   A: int[1,100] x N        (1行に空白区切りで N 個)
+//   S: string[a-z]{N}        (長さ N の文字列)
+//   G: string[.#]{W} x H     (文字列の配列は1行に1つ)
+//   P: perm N                (1..=N の順列)
+//   T: tree N                (N頂点の木, N-1行の辺)
+//   E: graph N, M            (N頂点M辺の単純グラフ)
+//
+// // 以降はコメント
+// ----------------------------------------------  //
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Num(i64),
+    Var(String),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug)]
+enum Kind {
+    Int { lo: Expr, hi: Expr },
+    Str { charset: Vec<char>, len: Expr },
+    Perm { n: Expr },
+    Tree { n: Expr },
+    Graph { n: Expr, m: Expr },
+}
+
+#[derive(Debug)]
+struct Item {
+    name: String,
+    kind: Kind,
+    repeat: Option<Expr>,
+}
+
+#[derive(Debug)]
+pub struct Spec {
+    lines: Vec<Vec<Item>>,
+}
+
+impl Expr {
+    fn eval(&self, env: &HashMap<String, i64>) -> anyhow::Result<i64> {
+        Ok(match self {
+            Expr::Num(v) => *v,
+            Expr::Var(name) => *env
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("変数 {} が未定義です", name))?,
+            Expr::Add(a, b) => checked(a.eval(env)?.checked_add(b.eval(env)?))?,
+            Expr::Sub(a, b) => checked(a.eval(env)?.checked_sub(b.eval(env)?))?,
+            Expr::Mul(a, b) => checked(a.eval(env)?.checked_mul(b.eval(env)?))?,
+            Expr::Div(a, b) => {
+                let d = b.eval(env)?;
+                if d == 0 {
+                    return Err(anyhow::anyhow!("0 で割ることはできません"));
+                }
+                checked(a.eval(env)?.checked_div(d))?
+            }
+        })
+    }
+}
+
+fn checked<T>(value: Option<T>) -> anyhow::Result<T> {
+    value.ok_or_else(|| anyhow::anyhow!("計算結果が 64bit 整数の範囲を超えました"))
+}
+
+struct ExprParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl ExprParser<'_> {
+    fn skip_ws(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn expr(&mut self) -> anyhow::Result<Expr> {
+        let mut lhs = self.term()?;
+        loop {
+            self.skip_ws();
+            match self.chars.peek() {
+                Some('+') => {
+                    self.chars.next();
+                    lhs = Expr::Add(Box::new(lhs), Box::new(self.term()?));
+                }
+                Some('-') => {
+                    self.chars.next();
+                    lhs = Expr::Sub(Box::new(lhs), Box::new(self.term()?));
+                }
+                _ => return Ok(lhs),
+            }
+        }
+    }
+
+    fn term(&mut self) -> anyhow::Result<Expr> {
+        let mut lhs = self.atom()?;
+        loop {
+            self.skip_ws();
+            match self.chars.peek() {
+                Some('*') => {
+                    self.chars.next();
+                    lhs = Expr::Mul(Box::new(lhs), Box::new(self.atom()?));
+                }
+                Some('/') => {
+                    self.chars.next();
+                    lhs = Expr::Div(Box::new(lhs), Box::new(self.atom()?));
+                }
+                _ => return Ok(lhs),
+            }
+        }
+    }
+
+    fn atom(&mut self) -> anyhow::Result<Expr> {
+        self.skip_ws();
+        match self.chars.peek().copied() {
+            Some('(') => {
+                self.chars.next();
+                let e = self.expr()?;
+                self.skip_ws();
+                if self.chars.next() != Some(')') {
+                    return Err(anyhow::anyhow!("')' が必要です"));
+                }
+                Ok(e)
+            }
+            Some('-') => {
+                self.chars.next();
+                Ok(Expr::Sub(Box::new(Expr::Num(0)), Box::new(self.atom()?)))
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let mut s = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_ascii_digit() || c == '_' {
+                        s.push(c);
+                        self.chars.next();
+                    } else if c == 'e' {
+                        // 1e5 のような表記
+                        self.chars.next();
+                        let mut exp = String::new();
+                        while let Some(&d) = self.chars.peek().filter(|d| d.is_ascii_digit()) {
+                            exp.push(d);
+                            self.chars.next();
+                        }
+                        let base: i64 = s.replace('_', "").parse()?;
+                        let value = exp
+                            .parse()
+                            .ok()
+                            .and_then(|exp| 10i64.checked_pow(exp))
+                            .and_then(|scale| base.checked_mul(scale))
+                            .ok_or_else(|| {
+                                anyhow::anyhow!("{}e{} は 64bit 整数の範囲を超えます", s, exp)
+                            })?;
+                        return Ok(Expr::Num(value));
+                    } else {
+                        break;
+                    }
+                }
+                Ok(Expr::Num(s.replace('_', "").parse()?))
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let mut s = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_alphanumeric() || c == '_' {
+                        s.push(c);
+                        self.chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                Ok(Expr::Var(s))
+            }
+            other => Err(anyhow::anyhow!("式を解釈できません: {:?}", other)),
+        }
+    }
+}
+
+fn parse_expr(s: &str) -> anyhow::Result<Expr> {
+    let mut p = ExprParser {
+        chars: s.chars().peekable(),
+    };
+    let e = p.expr()?;
+    p.skip_ws();
+    if let Some(c) = p.chars.next() {
+        return Err(anyhow::anyhow!("式 {} の {} 以降を解釈できません", s, c));
+    }
+    Ok(e)
+}
+
+fn split_top_level(s: &str, sep: char) -> Vec<&str> {
+    // 括弧の外側にある sep で分割する
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' | '{' | '(' => depth += 1,
+            ']' | '}' | ')' => depth -= 1,
+            _ if c == sep && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+fn starts_with_declaration(s: &str) -> bool {
+    // "NAME:" で始まるかどうか
+    match s.split_once(':') {
+        Some((name, _)) => {
+            let name = name.trim();
+            !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+fn parse_charset(s: &str) -> anyhow::Result<Vec<char>> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut charset = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if i + 2 < chars.len() && chars[i + 1] == '-' {
+            if chars[i] > chars[i + 2] {
+                return Err(anyhow::anyhow!(
+                    "文字範囲 {}-{} が不正です",
+                    chars[i],
+                    chars[i + 2]
+                ));
+            }
+            charset.extend(chars[i]..=chars[i + 2]);
+            i += 3;
+        } else {
+            charset.push(chars[i]);
+            i += 1;
+        }
+    }
+    if charset.is_empty() {
+        return Err(anyhow::anyhow!("文字集合が空です"));
+    }
+    Ok(charset)
+}
+
+fn parse_repeat(s: &str, name: &str) -> anyhow::Result<Option<Expr>> {
+    // 型の後ろに続く "x EXPR" を解釈する
+    let s = s.trim();
+    if s.is_empty() {
+        return Ok(None);
+    }
+    match s.strip_prefix('x') {
+        Some(count) if count.starts_with(char::is_whitespace) => Ok(Some(parse_expr(count)?)),
+        _ => Err(anyhow::anyhow!("{}: {} を解釈できません", name, s)),
+    }
+}
+
+fn parse_item(decl: &str) -> anyhow::Result<Item> {
+    let (name, ty) = decl
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("宣言 {} に ':' がありません", decl.trim()))?;
+    let name = name.trim().to_string();
+    let ty = ty.trim();
+
+    let (kind, repeat) = if let Some(rest) = ty.strip_prefix("int[") {
+        let (range, rest) = rest
+            .split_once(']')
+            .ok_or_else(|| anyhow::anyhow!("{}: ']' が必要です", name))?;
+        let bounds = split_top_level(range, ',');
+        if bounds.len() != 2 {
+            return Err(anyhow::anyhow!(
+                "{}: int[下限,上限] の形式で指定してください",
+                name
+            ));
+        }
+        let repeat = parse_repeat(rest, &name)?;
+        let kind = Kind::Int {
+            lo: parse_expr(bounds[0])?,
+            hi: parse_expr(bounds[1])?,
+        };
+        (kind, repeat)
+    } else if let Some(rest) = ty.strip_prefix("string[") {
+        let (charset, rest) = rest
+            .split_once(']')
+            .ok_or_else(|| anyhow::anyhow!("{}: ']' が必要です", name))?;
+        let (len, rest) = rest
+            .trim()
+            .strip_prefix('{')
+            .and_then(|r| r.split_once('}'))
+            .ok_or_else(|| anyhow::anyhow!("{}: 長さを {{N}} の形式で指定してください", name))?;
+        let repeat = parse_repeat(rest, &name)?;
+        let kind = Kind::Str {
+            charset: parse_charset(charset)?,
+            len: parse_expr(len)?,
+        };
+        (kind, repeat)
+    } else if let Some(n) = ty.strip_prefix("perm ") {
+        (Kind::Perm { n: parse_expr(n)? }, None)
+    } else if let Some(n) = ty.strip_prefix("tree ") {
+        (Kind::Tree { n: parse_expr(n)? }, None)
+    } else if let Some(rest) = ty.strip_prefix("graph ") {
+        let args = split_top_level(rest, ',');
+        if args.len() != 2 {
+            return Err(anyhow::anyhow!(
+                "{}: graph N, M の形式で指定してください",
+                name
+            ));
+        }
+        let kind = Kind::Graph {
+            n: parse_expr(args[0])?,
+            m: parse_expr(args[1])?,
+        };
+        (kind, None)
+    } else {
+        return Err(anyhow::anyhow!("{}: 型 {} は未対応です", name, ty));
+    };
+
+    Ok(Item { name, kind, repeat })
+}
+
+pub fn parse_spec(src: &str) -> anyhow::Result<Spec> {
+    /*
+    仕様ファイルを解釈する
+    args:
+        src: 仕様ファイルの内容
+    returns:
+        解釈済みの仕様
+    */
+    let mut lines = Vec::new();
+    for raw_line in src.lines() {
+        let line = raw_line.split("//").next().unwrap_or("");
+        for stmt in split_top_level(line, ';') {
+            if stmt.trim().is_empty() {
+                continue;
+            }
+            // 次の宣言が始まる位置の , でのみ分割する (graph N, M の , は分割しない)
+            let mut decls: Vec<String> = Vec::new();
+            for part in split_top_level(stmt, ',') {
+                match decls.last_mut() {
+                    Some(last) if !starts_with_declaration(part) => {
+                        last.push(',');
+                        last.push_str(part);
+                    }
+                    _ => decls.push(part.to_string()),
+                }
+            }
+            let items = decls
+                .iter()
+                .map(|d| parse_item(d))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            lines.push(items);
+        }
+    }
+    if lines.is_empty() {
+        return Err(anyhow::anyhow!("仕様ファイルに宣言がありません"));
+    }
+    Ok(Spec { lines })
+}
+
+// 個数や長さの上限 (誤った仕様で巨大な入力を作ろうとしてメモリを使い果たさないようにする)
+const MAX_COUNT: usize = 10_000_000;
+
+fn check_count(count: usize, name: &str) -> anyhow::Result<usize> {
+    if count > MAX_COUNT {
+        return Err(anyhow::anyhow!(
+            "{}: 個数 {} が上限 {} を超えています",
+            name,
+            count,
+            MAX_COUNT
+        ));
+    }
+    Ok(count)
+}
+
+fn eval_count(expr: &Expr, env: &HashMap<String, i64>, name: &str) -> anyhow::Result<usize> {
+    let v = expr.eval(env)?;
+    let count = usize::try_from(v).map_err(|_| anyhow::anyhow!("{}: 個数 {} が負です", name, v))?;
+    check_count(count, name)
+}
+
+fn gen_int(rng: &mut impl Rng, lo: i64, hi: i64, name: &str) -> anyhow::Result<i64> {
+    if lo > hi {
+        return Err(anyhow::anyhow!("{}: 範囲 [{}, {}] が空です", name, lo, hi));
+    }
+    Ok(rng.random_range(lo..=hi))
+}
+
+fn gen_tree(rng: &mut impl Rng, n: usize) -> Vec<(usize, usize)> {
+    // ランダムな親を選んで木を作り、頂点番号と辺の順番をシャッフルする
+    let mut label: Vec<usize> = (1..=n).collect();
+    label.shuffle(rng);
+    let mut edges: Vec<(usize, usize)> = (1..n)
+        .map(|v| {
+            let p = rng.random_range(0..v);
+            (label[p], label[v])
+        })
+        .collect();
+    edges.shuffle(rng);
+    edges
+}
+
+fn gen_graph(
+    rng: &mut impl Rng,
+    n: usize,
+    m: usize,
+    name: &str,
+) -> anyhow::Result<Vec<(usize, usize)>> {
+    let max_edges = checked(n.checked_mul(n.saturating_sub(1)))? / 2;
+    if m > max_edges {
+        return Err(anyhow::anyhow!(
+            "{}: {} 頂点の単純グラフに {} 本の辺は張れません",
+            name,
+            n,
+            m
+        ));
+    }
+    let mut used = HashSet::new();
+    let mut edges = Vec::with_capacity(m);
+    if m * 2 > max_edges {
+        // 密な場合は全辺からシャッフルして選ぶ
+        let mut all: Vec<(usize, usize)> = (1..=n)
+            .flat_map(|u| (u + 1..=n).map(move |v| (u, v)))
+            .collect();
+        all.shuffle(rng);
+        all.truncate(m);
+        return Ok(all);
+    }
+    while edges.len() < m {
+        let u = rng.random_range(1..=n);
+        let v = rng.random_range(1..=n);
+        if u == v || !used.insert((u.min(v), u.max(v))) {
+            continue;
+        }
+        edges.push((u, v));
+    }
+    Ok(edges)
+}
+
+impl Spec {
+    pub fn generate(&self, rng: &mut impl Rng) -> anyhow::Result<String> {
+        /*
+        仕様に従ってランダムな入力を1つ生成する
+        args:
+            rng: 乱数生成器
+        returns:
+            生成された入力 (末尾改行付き)
+        */
+        let mut env: HashMap<String, i64> = HashMap::new();
+        let mut out = String::new();
+
+        for line in &self.lines {
+            let mut fields = Vec::new();
+            for item in line {
+                let field = match &item.kind {
+                    Kind::Int { lo, hi } => {
+                        let (lo, hi) = (lo.eval(&env)?, hi.eval(&env)?);
+                        match &item.repeat {
+                            Some(count) => {
+                                let count = eval_count(count, &env, &item.name)?;
+                                (0..count)
+                                    .map(|_| {
+                                        gen_int(rng, lo, hi, &item.name).map(|v| v.to_string())
+                                    })
+                                    .collect::<anyhow::Result<Vec<_>>>()?
+                                    .join(" ")
+                            }
+                            None => {
+                                let v = gen_int(rng, lo, hi, &item.name)?;
+                                env.insert(item.name.clone(), v);
+                                v.to_string()
+                            }
+                        }
+                    }
+                    Kind::Str { charset, len } => {
+                        let len = eval_count(len, &env, &item.name)?;
+                        let count = match &item.repeat {
+                            Some(count) => eval_count(count, &env, &item.name)?,
+                            None => 1,
+                        };
+                        // 文字列の配列は全体の文字数も上限までにする
+                        check_count(len.saturating_mul(count), &item.name)?;
+                        (0..count)
+                            .map(|_| {
+                                (0..len)
+                                    .map(|_| charset[rng.random_range(0..charset.len())])
+                                    .collect::<String>()
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    }
+                    Kind::Perm { n } => {
+                        let n = eval_count(n, &env, &item.name)?;
+                        let mut p: Vec<usize> = (1..=n).collect();
+                        p.shuffle(rng);
+                        p.iter()
+                            .map(|v| v.to_string())
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    }
+                    Kind::Tree { n } => {
+                        let n = eval_count(n, &env, &item.name)?;
+                        gen_tree(rng, n)
+                            .iter()
+                            .map(|(u, v)| format!("{} {}", u, v))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    }
+                    Kind::Graph { n, m } => {
+                        let n = eval_count(n, &env, &item.name)?;
+                        let m = eval_count(m, &env, &item.name)?;
+                        gen_graph(rng, n, m, &item.name)?
+                            .iter()
+                            .map(|(u, v)| format!("{} {}", u, v))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    }
+                };
+                fields.push(field);
+            }
+            let text = fields.join(" ");
+            // 辺数0の木やグラフは行ごと省略する
+            let only_edges = line
+                .iter()
+                .all(|item| matches!(item.kind, Kind::Tree { .. } | Kind::Graph { .. }));
+            if !(text.is_empty() && only_edges) {
+                out.push_str(&text);
+                out.push('\n');
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn eval(s: &str) -> anyhow::Result<i64> {
+        let env = HashMap::from([("N".to_string(), 5)]);
+        parse_expr(s)?.eval(&env)
+    }
+
+    #[test]
+    fn evaluates_expressions() {
+        let cases = [
+            ("1+2*3", 7),
+            ("(1+2)*3", 9),
+            ("N*(N-1)/2", 10),
+            ("-N+1", -4),
+            ("2e5", 200_000),
+            ("1_000_000", 1_000_000),
+            ("9e18", 9_000_000_000_000_000_000),
+        ];
+        for (src, expected) in cases {
+            assert_eq!(eval(src).unwrap(), expected, "{}", src);
+        }
+    }
+
+    #[test]
+    fn rejects_overflow_and_invalid_expressions() {
+        for src in [
+            "1e19",
+            "10e18",
+            "9e18*2",
+            "9e18+9e18",
+            "-9e18-9e18",
+            "1/0",
+            "M",
+            "1+",
+            "(1",
+        ] {
+            assert!(eval(src).is_err(), "{}", src);
+        }
+    }
+
+    #[test]
+    fn parses_spec_lines() {
+        let spec = parse_spec(
+            "N: int[1,10], M: int[0,N*(N-1)/2]  // コメント\n\
+             A: int[1,100] x N; S: string[a-z]{N}\n\
+             E: graph N, M\n",
+        )
+        .unwrap();
+        let names: Vec<Vec<&str>> = spec
+            .lines
+            .iter()
+            .map(|line| line.iter().map(|item| item.name.as_str()).collect())
+            .collect();
+        assert_eq!(names, [vec!["N", "M"], vec!["A"], vec!["S"], vec!["E"]]);
+    }
+
+    #[test]
+    fn rejects_invalid_specs() {
+        let cases = [
+            "",
+            "// コメントのみ",
+            "N int[1,10]",
+            "N: int[1]",
+            "N: float[0,1]",
+            "S: string[z-a]{3}",
+            "S: string[a-z]",
+            "A: int[1,2] y 3",
+            "E: graph 3",
+        ];
+        for src in cases {
+            assert!(parse_spec(src).is_err(), "{:?}", src);
+        }
+    }
+
+    #[test]
+    fn generates_values_within_spec() {
+        let spec = parse_spec(
+            "N: int[3,6]\nA: int[-5,5] x N\nS: string[ab]{N}\nP: perm N\nT: tree N\nE: graph N, N-1",
+        )
+        .unwrap();
+        for seed in 0..20 {
+            let out = spec.generate(&mut StdRng::seed_from_u64(seed)).unwrap();
+            let lines: Vec<&str> = out.lines().collect();
+            let n: usize = lines[0].parse().unwrap();
+            assert!((3..=6).contains(&n));
+            let a: Vec<i64> = lines[1].split(' ').map(|v| v.parse().unwrap()).collect();
+            assert!(a.len() == n && a.iter().all(|v| (-5..=5).contains(v)));
+            assert!(lines[2].len() == n && lines[2].chars().all(|c| c == 'a' || c == 'b'));
+            let mut p: Vec<usize> = lines[3].split(' ').map(|v| v.parse().unwrap()).collect();
+            p.sort();
+            assert_eq!(p, (1..=n).collect::<Vec<_>>());
+            // 木の n-1 本とグラフの n-1 本の辺
+            assert_eq!(lines.len(), 4 + 2 * (n - 1));
+        }
+    }
+
+    #[test]
+    fn same_seed_generates_same_input() {
+        let spec = parse_spec("N: int[1,1e9]\nA: int[1,N] x 10").unwrap();
+        let a = spec.generate(&mut StdRng::seed_from_u64(42)).unwrap();
+        let b = spec.generate(&mut StdRng::seed_from_u64(42)).unwrap();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn rejects_impossible_values() {
+        for src in [
+            "N: int[5,1]",
+            "A: int[1,2] x -1",
+            "E: graph 3, 4",
+            // 個数や文字数が上限を超える
+            "A: int[1,2] x 1000000000000",
+            "P: perm 1e8",
+            "S: string[a]{1e4} x 1e4",
+        ] {
+            let spec = parse_spec(src).unwrap();
+            assert!(
+                spec.generate(&mut StdRng::seed_from_u64(0)).is_err(),
+                "{}",
+                src
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_overflowing_graph_sizes() {
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!(gen_graph(&mut rng, usize::MAX, 1, "E").is_err());
+        assert_eq!(gen_graph(&mut rng, 4, 6, "E").unwrap().len(), 6);
+    }
+}
@@ -0,0 +1,1066 @@
+use scraper::{ElementRef, Html, Node, Selector};
+use std::collections::{HashMap, HashSet};
+
+// ----------------------------------------------  //
+// 問題文の「入力」欄 (<pre> 内の <var>) から入力形式を推定し、
+// テンプレートに変数宣言と読み込みコードを書き込む
+// 解釈できない行はコメントとして残す
+// ----------------------------------------------  //
+
+#[derive(Debug, Clone, PartialEq)]
+enum FormatLine {
+    // N M
+    Scalars(Vec<String>),
+    // A_1 A_2 ... A_N
+    Array {
+        name: String,
+        len: String,
+    },
+    // A_1 B_1 / \vdots / A_N B_N
+    Columns {
+        names: Vec<String>,
+        len: String,
+    },
+    // A_{1,1} ... A_{1,W} / \vdots / A_{H,1} ... A_{H,W}
+    // spaced が false の場合は文字のグリッド (C_{1,1}C_{1,2}... または行ごとの文字列 S_1 / \vdots / S_H)
+    Grid {
+        name: String,
+        rows: String,
+        cols: String,
+        spaced: bool,
+    },
+    // \mathrm{query}_1 / \vdots / \mathrm{query}_Q (各クエリの形式は別の <pre> に書かれている)
+    Queries {
+        count: String,
+        kinds: Vec<QueryKind>,
+    },
+    Unknown(String),
+}
+
+// 「1 x y」のようなクエリの1つの形式
+#[derive(Debug, Clone, PartialEq)]
+struct QueryKind {
+    // 先頭の種類を表す数 (形式が1つだけの場合は None)
+    tag: Option<String>,
+    // 種類に続く変数 (解釈できない場合は None)
+    fields: Option<Vec<String>>,
+    text: String,
+}
+
+#[derive(Debug)]
+pub struct InputFormat {
+    lines: Vec<FormatLine>,
+    strings: HashSet<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Var { base: String, index: Vec<String> },
+    HDots,
+    VDots,
+}
+
+// <pre> 内の1行
+#[derive(Debug, Default)]
+struct PreRow {
+    text: String,
+    tokens: Vec<Token>,
+    // 変数同士が空白で区切られているか
+    spaced: bool,
+    // 変数以外の文字を含むか
+    has_text: bool,
+    // 変数以外の文字
+    plain: String,
+}
+
+// 1行を解釈した中間表現
+#[derive(Debug)]
+enum RawLine {
+    Line(FormatLine),
+    VDots,
+    // A_i B_i のように同じ添字を持つ変数の並び
+    Row {
+        names: Vec<String>,
+        index: String,
+    },
+    // A_{i,1} ... A_{i,W}
+    GridRow {
+        name: String,
+        row: String,
+        cols: String,
+        spaced: bool,
+    },
+}
+
+fn skip_group(bytes: &[u8], mut i: usize) -> usize {
+    // i にある { から対応する } の直後までを読み飛ばす
+    let mut depth = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    i
+}
+
+fn split_var(var: &str) -> (Vec<Token>, bool) {
+    /*
+    A_{1,1}A_{1,2}\ldots A_{1,W} のように1つの <var> に書かれた行を変数ごとに分ける
+    returns:
+        変数の並びと、変数同士が空白で区切られているか
+    */
+    if var.contains("\\rm ") {
+        return (vec![parse_token(var)], false);
+    }
+    let bytes = var.trim().as_bytes();
+    let mut pieces = Vec::new();
+    let mut spaced = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i].is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        if bytes[i] == b'\\' {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
+                i += 1;
+            }
+            if bytes.get(i) == Some(&b'{') {
+                i = skip_group(bytes, i);
+            }
+        } else {
+            while i < bytes.len() && bytes[i].is_ascii_alphanumeric() {
+                i += 1;
+            }
+        }
+        if bytes.get(i) == Some(&b'_') {
+            i += 1;
+            if bytes.get(i) == Some(&b'{') {
+                i = skip_group(bytes, i);
+            } else if i < bytes.len() {
+                i += 1;
+            }
+        }
+        // 変数として読めない文字を含む場合は分けない
+        if i == start || !(bytes[start].is_ascii_alphabetic() || bytes[start] == b'\\') {
+            return (vec![parse_token(var)], false);
+        }
+        let piece = &var.trim()[start..i];
+        let token = parse_token(piece);
+        let prev_is_var = matches!(pieces.last(), Some(Token::Var { .. }));
+        if prev_is_var
+            && matches!(token, Token::Var { .. })
+            && bytes[start - 1].is_ascii_whitespace()
+        {
+            spaced = true;
+        }
+        pieces.push(token);
+    }
+    if pieces.len() <= 1 {
+        return (vec![parse_token(var)], false);
+    }
+    (pieces, spaced)
+}
+
+fn parse_token(var: &str) -> Token {
+    let var = var.trim();
+    match var {
+        "\\ldots" | "\\dots" | "\\cdots" | "…" => return Token::HDots,
+        "\\vdots" | "⋮" => return Token::VDots,
+        _ => {}
+    }
+    match var.split_once('_') {
+        Some((base, index)) => {
+            let index = index
+                .trim()
+                .trim_start_matches('{')
+                .trim_end_matches('}')
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .collect();
+            Token::Var {
+                base: plain_name(base),
+                index,
+            }
+        }
+        None => Token::Var {
+            base: plain_name(var),
+            index: Vec::new(),
+        },
+    }
+}
+
+fn plain_name(name: &str) -> String {
+    // \mathrm{query} や \rm query のような書体の指定を取り除く
+    let name = name.trim();
+    if let Some(rest) = name.strip_prefix("\\rm ") {
+        return rest.trim().to_string();
+    }
+    if let Some(rest) = name.strip_prefix('\\')
+        && let Some((command, inner)) = rest.split_once('{')
+        && command.chars().all(|c| c.is_ascii_alphabetic())
+        && let Some(inner) = inner.strip_suffix('}')
+    {
+        return inner.trim().to_string();
+    }
+    name.to_string()
+}
+
+fn is_query_name(name: &str) -> bool {
+    name.eq_ignore_ascii_case("query")
+}
+
+fn is_identifier(s: &str) -> bool {
+    s.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn to_cpp_expr(s: &str) -> Option<String> {
+    // 2N や N+1 のような添字を C++ の式に変換する
+    let s = s.replace("\\times", "*").replace(' ', "");
+    let mut expr = String::new();
+    let mut prev: Option<char> = None;
+    for c in s.chars() {
+        if !(c.is_ascii_alphanumeric() || "+-*/()".contains(c)) {
+            return None;
+        }
+        if c.is_ascii_alphabetic() && prev.is_some_and(|p| p.is_ascii_digit()) {
+            expr.push('*');
+        }
+        expr.push(c);
+        prev = Some(c);
+    }
+    if expr.is_empty() { None } else { Some(expr) }
+}
+
+fn length_from_range(first: &str, last: &str) -> Option<String> {
+    // 添字の範囲 first..=last から要素数を求める
+    let last = to_cpp_expr(last)?;
+    match first {
+        "1" => Some(last),
+        "0" => match last.strip_suffix("-1") {
+            Some(len) => Some(len.to_string()),
+            None => Some(format!("{}+1", last)),
+        },
+        _ => None,
+    }
+}
+
+fn classify_line(row: &PreRow) -> RawLine {
+    let PreRow {
+        text,
+        tokens,
+        spaced,
+        has_text,
+        ..
+    } = row;
+    let spaced = *spaced;
+    let unknown = || RawLine::Line(FormatLine::Unknown(text.trim().to_string()));
+
+    if tokens == &[Token::VDots] && !has_text {
+        return RawLine::VDots;
+    }
+    let valid_names = tokens.iter().all(|t| match t {
+        Token::Var { base, .. } => is_identifier(base),
+        _ => true,
+    });
+    // 変数以外の文字が含まれる行は解釈しない
+    if tokens.is_empty() || *has_text || !valid_names {
+        return unknown();
+    }
+
+    let vars: Vec<(&String, &Vec<String>)> = tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::Var { base, index } => Some((base, index)),
+            _ => None,
+        })
+        .collect();
+    let has_hdots = tokens.contains(&Token::HDots);
+
+    // N M
+    if !has_hdots && vars.iter().all(|(_, index)| index.is_empty()) {
+        return RawLine::Line(FormatLine::Scalars(
+            vars.iter().map(|(base, _)| base.to_string()).collect(),
+        ));
+    }
+
+    // A_1 A_2 ... A_N
+    if has_hdots
+        && vars
+            .iter()
+            .all(|(base, index)| *base == vars[0].0 && index.len() == 1)
+        && let (Some(first), Some(last)) = (vars.first(), vars.last())
+    {
+        return match length_from_range(&first.1[0], &last.1[0]) {
+            Some(len) => RawLine::Line(FormatLine::Array {
+                name: first.0.to_string(),
+                len,
+            }),
+            None => unknown(),
+        };
+    }
+
+    // A_{i,1} ... A_{i,W}
+    if has_hdots
+        && vars
+            .iter()
+            .all(|(base, index)| *base == vars[0].0 && index.len() == 2 && index[0] == vars[0].1[0])
+        && let (Some(first), Some(last)) = (vars.first(), vars.last())
+    {
+        return match length_from_range(&first.1[1], &last.1[1]) {
+            Some(cols) => RawLine::GridRow {
+                name: first.0.to_string(),
+                row: first.1[0].clone(),
+                cols,
+                spaced,
+            },
+            None => unknown(),
+        };
+    }
+
+    // A_i B_i
+    if !has_hdots
+        && vars
+            .iter()
+            .all(|(_, index)| index.len() == 1 && index[0] == vars[0].1[0])
+    {
+        return RawLine::Row {
+            names: vars.iter().map(|(base, _)| base.to_string()).collect(),
+            index: vars[0].1[0].clone(),
+        };
+    }
+
+    unknown()
+}
+
+fn merge_vertical(raw: Vec<RawLine>) -> Vec<FormatLine> {
+    // 「先頭行 (/ 2行目 ...) / \vdots / 末尾行」の並びを1つの配列にまとめる
+    let mut lines = Vec::new();
+    let mut i = 0;
+    while i < raw.len() {
+        // \vdots の前に続く同じ形の行 (S_1 / S_2 / \vdots / S_N の S_2) は読み飛ばす
+        let mut vdots = i + 1;
+        while vdots < raw.len() && same_shape(&raw[i], &raw[vdots]) {
+            vdots += 1;
+        }
+        if vdots + 1 < raw.len() && matches!(raw[vdots], RawLine::VDots) {
+            match (&raw[i], &raw[vdots + 1]) {
+                (
+                    RawLine::Row {
+                        names,
+                        index: first,
+                    },
+                    RawLine::Row {
+                        names: last_names,
+                        index: last,
+                    },
+                ) if names == last_names => {
+                    if let Some(len) = length_from_range(first, last) {
+                        lines.push(FormatLine::Columns {
+                            names: names.clone(),
+                            len,
+                        });
+                        i = vdots + 2;
+                        continue;
+                    }
+                }
+                (
+                    RawLine::GridRow {
+                        name,
+                        row: first,
+                        cols,
+                        spaced,
+                    },
+                    RawLine::GridRow {
+                        name: last_name,
+                        row: last,
+                        ..
+                    },
+                ) if name == last_name => {
+                    if let Some(rows) = length_from_range(first, last) {
+                        lines.push(FormatLine::Grid {
+                            name: name.clone(),
+                            rows,
+                            cols: cols.clone(),
+                            spaced: *spaced,
+                        });
+                        i = vdots + 2;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+        }
+        lines.push(match &raw[i] {
+            RawLine::Line(line) => line.clone(),
+            // \vdots を伴わずに現れた A_1 B_1 などは解釈しない
+            other => FormatLine::Unknown(raw_line_text(other)),
+        });
+        i += 1;
+    }
+    lines
+}
+
+fn same_shape(a: &RawLine, b: &RawLine) -> bool {
+    match (a, b) {
+        (RawLine::Row { names, .. }, RawLine::Row { names: other, .. }) => names == other,
+        (RawLine::GridRow { name, .. }, RawLine::GridRow { name: other, .. }) => name == other,
+        _ => false,
+    }
+}
+
+fn raw_line_text(raw: &RawLine) -> String {
+    match raw {
+        RawLine::VDots => "\\vdots".to_string(),
+        RawLine::Row { names, index } => names
+            .iter()
+            .map(|n| format!("{}_{}", n, index))
+            .collect::<Vec<_>>()
+            .join(" "),
+        RawLine::GridRow {
+            name, row, cols, ..
+        } => {
+            format!("{}_{{{},1}} ... {}_{{{},{}}}", name, row, name, row, cols)
+        }
+        RawLine::Line(_) => String::new(),
+    }
+}
+
+fn section_of<'a>(document: &'a Html, title: &str) -> Option<ElementRef<'a>> {
+    // 見出しが title のセクションを探す (日本語の見出しは日本語版にのみ存在する)
+    let h3_selector = Selector::parse("h3").unwrap();
+    document
+        .select(&h3_selector)
+        .find(|h3| h3.text().collect::<String>().trim() == title)
+        .and_then(|h3| h3.parent())
+        .and_then(ElementRef::wrap)
+}
+
+fn string_length(li: ElementRef) -> Option<String> {
+    // 「長さ W の文字列」の W (添字のない変数か数の場合のみ)
+    let mut text = String::new();
+    for child in li.children() {
+        match child.value() {
+            Node::Text(part) => text.push_str(part),
+            Node::Element(element) if element.name() == "var" => {
+                if text.trim_end().ends_with("長さ") {
+                    let var = ElementRef::wrap(child)?.text().collect::<String>();
+                    return match parse_token(&var) {
+                        Token::Var { base, index } if index.is_empty() => to_cpp_expr(&base),
+                        _ => None,
+                    };
+                }
+                text.clear();
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn string_variables(document: &Html) -> (HashSet<String>, HashMap<String, String>) {
+    /*
+    制約欄で文字列と書かれている変数を集める
+    returns:
+        文字列の変数と、そのうち長さが決まっているものの長さ
+    */
+    let mut strings = HashSet::new();
+    let mut lengths = HashMap::new();
+    let Some(section) = section_of(document, "制約") else {
+        return (strings, lengths);
+    };
+    let li_selector = Selector::parse("li").unwrap();
+    let var_selector = Selector::parse("var").unwrap();
+    for li in section.select(&li_selector) {
+        let text = li.text().collect::<String>();
+        if !(text.contains("文字列") || text.contains("英小文字") || text.contains("英大文字"))
+        {
+            continue;
+        }
+        // 先頭の変数を文字列の変数とみなす (「S は長さ N の文字列」の N は含めない)
+        if let Some(var) = li.select(&var_selector).next()
+            && let Token::Var { base, .. } = parse_token(&var.text().collect::<String>())
+            && is_identifier(&base)
+        {
+            if let Some(len) = string_length(li) {
+                lengths.insert(base.clone(), len);
+            }
+            strings.insert(base);
+        }
+    }
+    (strings, lengths)
+}
+
+fn pre_rows(pre: ElementRef) -> Vec<PreRow> {
+    // <pre> の子要素を行ごとに分ける (空行は除く)
+    let mut rows = vec![PreRow::default()];
+    for child in pre.children() {
+        match child.value() {
+            Node::Text(text) => {
+                for (i, part) in text.split('\n').enumerate() {
+                    if i > 0 {
+                        rows.push(PreRow::default());
+                    }
+                    let row = rows.last_mut().unwrap();
+                    row.text.push_str(part);
+                    row.plain.push_str(part);
+                    if !part.trim().is_empty() {
+                        row.has_text = true;
+                    } else if !part.is_empty() && !row.tokens.is_empty() {
+                        row.spaced = true;
+                    }
+                }
+            }
+            Node::Element(_) => {
+                let Some(element) = ElementRef::wrap(child) else {
+                    continue;
+                };
+                let var = element.text().collect::<String>();
+                let (tokens, spaced) = split_var(&var);
+                let row = rows.last_mut().unwrap();
+                row.text.push_str(&var);
+                row.tokens.extend(tokens);
+                row.spaced |= spaced;
+            }
+            _ => {}
+        }
+    }
+    rows.retain(|row| !row.text.trim().is_empty());
+    rows
+}
+
+fn parse_query_kind(row: &PreRow) -> QueryKind {
+    // 先頭の数 (<var> で囲まれていないことが多い) を種類、残りの変数を読み込む値とする
+    let text = row.text.trim().to_string();
+    let plain = row.plain.trim();
+    let mut tokens = row.tokens.as_slice();
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    let tag = if plain.is_empty()
+        && let Some(Token::Var { base, index }) = tokens.first()
+        && index.is_empty()
+        && is_number(base)
+    {
+        tokens = &tokens[1..];
+        Some(base.clone())
+    } else if plain.is_empty() {
+        None
+    } else if is_number(plain) && text.starts_with(plain) {
+        Some(plain.to_string())
+    } else {
+        return QueryKind {
+            tag: None,
+            fields: None,
+            text,
+        };
+    };
+    let fields = tokens
+        .iter()
+        .map(|token| match token {
+            Token::Var { base, .. } if is_identifier(base) => Some(base.clone()),
+            _ => None,
+        })
+        .collect();
+    QueryKind { tag, fields, text }
+}
+
+fn query_kinds(section: ElementRef, pre_selector: &Selector) -> Vec<QueryKind> {
+    // 入力欄の2つ目以降の <pre> に1つずつ書かれたクエリの形式を集める
+    section
+        .select(pre_selector)
+        .skip(1)
+        .filter_map(|pre| pre_rows(pre).first().map(parse_query_kind))
+        .collect()
+}
+
+fn refine(line: FormatLine, kinds: &[QueryKind], lengths: &HashMap<String, String>) -> FormatLine {
+    // 1変数の縦の並びのうち、クエリと長さの決まった文字列を見分ける
+    let FormatLine::Columns { names, len } = line else {
+        return line;
+    };
+    match names.as_slice() {
+        [name] if is_query_name(name) => FormatLine::Queries {
+            count: len,
+            kinds: kinds.to_vec(),
+        },
+        [name] if lengths.contains_key(name) => FormatLine::Grid {
+            name: name.clone(),
+            rows: len,
+            cols: lengths[name].clone(),
+            spaced: false,
+        },
+        _ => FormatLine::Columns { names, len },
+    }
+}
+
+pub fn parse_input_format(document: &Html) -> Option<InputFormat> {
+    /*
+    問題ページの「入力」欄から入力形式を推定する
+    args:
+        document: 問題ページのHTML
+    returns:
+        入力形式 (入力欄が見つからない場合は None)
+    */
+    let section = section_of(document, "入力")?;
+    let pre_selector = Selector::parse("pre").unwrap();
+    let pre = section.select(&pre_selector).next()?;
+
+    let raw = pre_rows(pre).iter().map(classify_line).collect();
+    let kinds = query_kinds(section, &pre_selector);
+    let (strings, lengths) = string_variables(document);
+    let lines: Vec<FormatLine> = merge_vertical(raw)
+        .into_iter()
+        .map(|line| refine(line, &kinds, &lengths))
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+    Some(InputFormat { lines, strings })
+}
+
+impl InputFormat {
+    fn type_of(&self, name: &str) -> &'static str {
+        if self.strings.contains(name) {
+            "string"
+        } else {
+            "long long"
+        }
+    }
+
+    fn reading_code(&self) -> Vec<String> {
+        let mut code = Vec::new();
+        for line in &self.lines {
+            match line {
+                FormatLine::Scalars(names) => {
+                    let mut decls: Vec<(&str, Vec<&str>)> = Vec::new();
+                    for name in names {
+                        let ty = self.type_of(name);
+                        match decls.iter_mut().find(|(t, _)| *t == ty) {
+                            Some((_, group)) => group.push(name),
+                            None => decls.push((ty, vec![name])),
+                        }
+                    }
+                    for (ty, group) in decls {
+                        code.push(format!("{} {};", ty, group.join(", ")));
+                    }
+                    code.push(format!("cin >> {};", names.join(" >> ")));
+                }
+                FormatLine::Array { name, len } => {
+                    code.push(format!("vector<{}> {}({});", self.type_of(name), name, len));
+                    code.push(format!(
+                        "for (int i = 0; i < {}; i++) cin >> {}[i];",
+                        len, name
+                    ));
+                }
+                FormatLine::Columns { names, len } => {
+                    let mut decls: Vec<(&str, Vec<String>)> = Vec::new();
+                    for name in names {
+                        let ty = self.type_of(name);
+                        let decl = format!("{}({})", name, len);
+                        match decls.iter_mut().find(|(t, _)| *t == ty) {
+                            Some((_, group)) => group.push(decl),
+                            None => decls.push((ty, vec![decl])),
+                        }
+                    }
+                    for (ty, group) in decls {
+                        code.push(format!("vector<{}> {};", ty, group.join(", ")));
+                    }
+                    let reads = names
+                        .iter()
+                        .map(|n| format!("{}[i]", n))
+                        .collect::<Vec<_>>()
+                        .join(" >> ");
+                    code.push(format!(
+                        "for (int i = 0; i < {}; i++) cin >> {};",
+                        len, reads
+                    ));
+                }
+                FormatLine::Grid {
+                    name,
+                    rows,
+                    cols,
+                    spaced: false,
+                } => {
+                    code.push(format!(
+                        "vector<string> {}({}); // 各行の長さは {}",
+                        name, rows, cols
+                    ));
+                    code.push(format!(
+                        "for (int i = 0; i < {}; i++) cin >> {}[i];",
+                        rows, name
+                    ));
+                }
+                FormatLine::Grid {
+                    name,
+                    rows,
+                    cols,
+                    spaced: true,
+                } => {
+                    let ty = self.type_of(name);
+                    code.push(format!(
+                        "vector<vector<{}>> {}({}, vector<{}>({}));",
+                        ty, name, rows, ty, cols
+                    ));
+                    code.push(format!(
+                        "for (int i = 0; i < {}; i++) for (int j = 0; j < {}; j++) cin >> {}[i][j];",
+                        rows, cols, name
+                    ));
+                }
+                FormatLine::Queries { count, kinds } => {
+                    code.push(format!("for (int q = 0; q < {}; q++) {{", count));
+                    code.extend(self.query_code(kinds).iter().map(|l| format!("    {}", l)));
+                    code.push("}".to_string());
+                }
+                FormatLine::Unknown(text) => {
+                    code.push(format!("// TODO: {}", text));
+                }
+            }
+        }
+        code
+    }
+
+    fn read_fields(&self, fields: &[String]) -> Vec<String> {
+        // クエリの値を宣言して読み込む
+        if fields.is_empty() {
+            return Vec::new();
+        }
+        let mut decls: Vec<(&str, Vec<&str>)> = Vec::new();
+        for name in fields {
+            let ty = self.type_of(name);
+            match decls.iter_mut().find(|(t, _)| *t == ty) {
+                Some((_, group)) => group.push(name),
+                None => decls.push((ty, vec![name])),
+            }
+        }
+        let mut code: Vec<String> = decls
+            .iter()
+            .map(|(ty, group)| format!("{} {};", ty, group.join(", ")))
+            .collect();
+        code.push(format!("cin >> {};", fields.join(" >> ")));
+        code
+    }
+
+    fn query_code(&self, kinds: &[QueryKind]) -> Vec<String> {
+        /*
+        1つのクエリを読み込むコード
+        形式が1つだけならその値を読み、複数あれば先頭の種類で分岐する
+        */
+        if let [
+            QueryKind {
+                tag: None,
+                fields: Some(fields),
+                ..
+            },
+        ] = kinds
+        {
+            return self.read_fields(fields);
+        }
+        if kinds.is_empty() || kinds.iter().any(|kind| kind.tag.is_none()) {
+            let mut code = vec!["// TODO: クエリの形式".to_string()];
+            code.extend(kinds.iter().map(|kind| format!("// {}", kind.text)));
+            return code;
+        }
+
+        let mut code = vec!["int type;".to_string(), "cin >> type;".to_string()];
+        for (i, kind) in kinds.iter().enumerate() {
+            let tag = kind.tag.as_deref().unwrap_or_default();
+            let keyword = if i == 0 { "if" } else { "} else if" };
+            code.push(format!("{} (type == {}) {{", keyword, tag));
+            let body = match &kind.fields {
+                Some(fields) => self.read_fields(fields),
+                None => vec![format!("// TODO: {}", kind.text)],
+            };
+            code.extend(body.iter().map(|l| format!("    {}", l)));
+        }
+        code.push("}".to_string());
+        code
+    }
+
+    pub fn render(&self, template: &str) -> String {
+        /*
+        テンプレートの main 関数の先頭 (sync_with_stdio の直後) に読み込みコードを書き込む
+        args:
+            template: 元のテンプレート
+        returns:
+            読み込みコードを書き込んだソース
+        */
+        let code = self.reading_code();
+        let uses_vector = code.iter().any(|l| l.starts_with("vector<"));
+        let uses_string = code.iter().any(|l| l.contains("string"));
+
+        let mut out = String::new();
+        let mut inserted = false;
+        for line in template.lines() {
+            out.push_str(line);
+            out.push('\n');
+            if line.trim() == "#include <iostream>" {
+                if uses_string && !template.contains("#include <string>") {
+                    out.push_str("#include <string>\n");
+                }
+                if uses_vector && !template.contains("#include <vector>") {
+                    out.push_str("#include <vector>\n");
+                }
+            }
+            if !inserted && line.contains("sync_with_stdio") {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                out.push('\n');
+                for code_line in &code {
+                    out.push_str(indent);
+                    out.push_str(code_line);
+                    out.push('\n');
+                }
+                inserted = true;
+            }
+        }
+        if !inserted {
+            // 挿入位置が見つからない場合は元のテンプレートのままにする
+            return template.to_string();
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(input: &str, constraints: &str) -> Html {
+        Html::parse_document(&format!(
+            r#"<div class="part"><section><h3>制約</h3><ul>{}</ul></section></div>
+<div class="part"><section><h3>入力</h3><p>入力は以下の形式で標準入力から与えられる。</p>
+{}
+</section></div>"#,
+            constraints, input
+        ))
+    }
+
+    fn reading_code(input: &str, constraints: &str) -> Vec<String> {
+        parse_input_format(&page(input, constraints))
+            .unwrap()
+            .reading_code()
+    }
+
+    #[test]
+    fn reads_scalars_arrays_and_columns() {
+        let cases: [(&str, &str, &[&str]); 3] = [
+            (
+                // ABC 300 A
+                "<pre><var>N</var> <var>A</var> <var>B</var>\n<var>C_1</var> <var>C_2</var> <var>\\ldots</var> <var>C_N</var>\n</pre>",
+                "",
+                &[
+                    "long long N, A, B;",
+                    "cin >> N >> A >> B;",
+                    "vector<long long> C(N);",
+                    "for (int i = 0; i < N; i++) cin >> C[i];",
+                ],
+            ),
+            (
+                // ABC 302 B のような 0 始まりの添字
+                "<pre><var>N</var>\n<var>A_0</var> <var>A_1</var> <var>\\dots</var> <var>A_{N-1}</var>\n</pre>",
+                "",
+                &[
+                    "long long N;",
+                    "cin >> N;",
+                    "vector<long long> A(N);",
+                    "for (int i = 0; i < N; i++) cin >> A[i];",
+                ],
+            ),
+            (
+                // ABC 309 C
+                "<pre><var>N</var> <var>K</var>\n<var>a_1</var> <var>b_1</var>\n<var>\\vdots</var>\n<var>a_N</var> <var>b_N</var>\n</pre>",
+                "",
+                &[
+                    "long long N, K;",
+                    "cin >> N >> K;",
+                    "vector<long long> a(N), b(N);",
+                    "for (int i = 0; i < N; i++) cin >> a[i] >> b[i];",
+                ],
+            ),
+        ];
+        for (input, constraints, expected) in cases {
+            assert_eq!(reading_code(input, constraints), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn reads_grids() {
+        let cases: [(&str, &str, &[&str]); 4] = [
+            (
+                // ABC 300 B (1行全体が1つの <var>)
+                "<pre><var>H</var> <var>W</var>\n<var>A_{1,1}A_{1,2}\\ldots A_{1,W}</var>\n<var>\\vdots</var>\n<var>A_{H,1}A_{H,2}\\ldots A_{H,W}</var>\n</pre>",
+                "",
+                &[
+                    "long long H, W;",
+                    "cin >> H >> W;",
+                    "vector<string> A(H); // 各行の長さは W",
+                    "for (int i = 0; i < H; i++) cin >> A[i];",
+                ],
+            ),
+            (
+                // 文字ごとに <var> が分かれている場合
+                "<pre><var>H</var> <var>W</var>\n<var>C_{1,1}</var><var>C_{1,2}</var><var>\\ldots</var><var>C_{1,W}</var>\n<var>\\vdots</var>\n<var>C_{H,1}</var><var>C_{H,2}</var><var>\\ldots</var><var>C_{H,W}</var>\n</pre>",
+                "",
+                &[
+                    "long long H, W;",
+                    "cin >> H >> W;",
+                    "vector<string> C(H); // 各行の長さは W",
+                    "for (int i = 0; i < H; i++) cin >> C[i];",
+                ],
+            ),
+            (
+                // ABC 311 C のような行ごとの文字列
+                "<pre><var>N</var> <var>M</var>\n<var>S_1</var>\n<var>S_2</var>\n<var>\\vdots</var>\n<var>S_N</var>\n</pre>",
+                "<li><var>S_i</var> は <code>#</code> と <code>.</code> からなる長さ <var>M</var> の文字列</li>",
+                &[
+                    "long long N, M;",
+                    "cin >> N >> M;",
+                    "vector<string> S(N); // 各行の長さは M",
+                    "for (int i = 0; i < N; i++) cin >> S[i];",
+                ],
+            ),
+            (
+                // ABC 305 B のような空白区切りの数のグリッド
+                "<pre><var>H</var> <var>W</var>\n<var>A_{1,1}</var> <var>\\ldots</var> <var>A_{1,W}</var>\n<var>\\vdots</var>\n<var>A_{H,1}</var> <var>\\ldots</var> <var>A_{H,W}</var>\n</pre>",
+                "",
+                &[
+                    "long long H, W;",
+                    "cin >> H >> W;",
+                    "vector<vector<long long>> A(H, vector<long long>(W));",
+                    "for (int i = 0; i < H; i++) for (int j = 0; j < W; j++) cin >> A[i][j];",
+                ],
+            ),
+        ];
+        for (input, constraints, expected) in cases {
+            assert_eq!(reading_code(input, constraints), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn strings_of_varying_length_are_not_grids() {
+        let code = reading_code(
+            "<pre><var>N</var>\n<var>S_1</var>\n<var>\\vdots</var>\n<var>S_N</var>\n</pre>",
+            "<li><var>S_i</var> は英小文字からなる文字列</li>",
+        );
+        assert_eq!(
+            code[2..],
+            [
+                "vector<string> S(N);",
+                "for (int i = 0; i < N; i++) cin >> S[i];"
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_queries_by_type() {
+        // ABC 278 D
+        let input = "<pre><var>N</var>\n<var>A_1</var> <var>A_2</var> <var>\\dots</var> <var>A_N</var>\n<var>Q</var>\n<var>\\mathrm{query}_1</var>\n<var>\\mathrm{query}_2</var>\n<var>\\vdots</var>\n<var>\\mathrm{query}_Q</var>\n</pre>\n\
+<p>ここで、<var>\\mathrm{query}_q</var> は <var>q</var> 番目のクエリを表し、次のいずれかの形式で与えられる。</p>\n\
+<pre>1 <var>x_q</var>\n</pre>\n<pre>2 <var>i_q</var> <var>x_q</var>\n</pre>\n<pre>3 <var>i_q</var>\n</pre>";
+        assert_eq!(
+            reading_code(input, "")[4..],
+            [
+                "long long Q;",
+                "cin >> Q;",
+                "for (int q = 0; q < Q; q++) {",
+                "    int type;",
+                "    cin >> type;",
+                "    if (type == 1) {",
+                "        long long x;",
+                "        cin >> x;",
+                "    } else if (type == 2) {",
+                "        long long i, x;",
+                "        cin >> i >> x;",
+                "    } else if (type == 3) {",
+                "        long long i;",
+                "        cin >> i;",
+                "    }",
+                "}",
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_query_variants() {
+        let cases: [(&str, &str, &[&str]); 3] = [
+            (
+                // 種類の数も <var> で書かれ、文字列を含む場合
+                "<pre><var>Q</var>\n<var>\\text{query}_1</var>\n<var>\\vdots</var>\n<var>\\text{query}_Q</var>\n</pre>\n<pre><var>1</var> <var>c</var> <var>x</var>\n</pre>\n<pre><var>2</var>\n</pre>",
+                "<li><var>c</var> は英小文字</li>",
+                &[
+                    "long long Q;",
+                    "cin >> Q;",
+                    "for (int q = 0; q < Q; q++) {",
+                    "    int type;",
+                    "    cin >> type;",
+                    "    if (type == 1) {",
+                    "        string c;",
+                    "        long long x;",
+                    "        cin >> c >> x;",
+                    "    } else if (type == 2) {",
+                    "    }",
+                    "}",
+                ],
+            ),
+            (
+                // 形式が1つだけの場合は種類を読まない
+                "<pre><var>Q</var>\n<var>\\rm query_1</var>\n<var>\\vdots</var>\n<var>\\rm query_Q</var>\n</pre>\n<pre><var>u</var> <var>v</var>\n</pre>",
+                "",
+                &[
+                    "long long Q;",
+                    "cin >> Q;",
+                    "for (int q = 0; q < Q; q++) {",
+                    "    long long u, v;",
+                    "    cin >> u >> v;",
+                    "}",
+                ],
+            ),
+            (
+                // 形式が書かれていない場合
+                "<pre><var>Q</var>\n<var>\\mathrm{query}_1</var>\n<var>\\vdots</var>\n<var>\\mathrm{query}_Q</var>\n</pre>",
+                "",
+                &[
+                    "long long Q;",
+                    "cin >> Q;",
+                    "for (int q = 0; q < Q; q++) {",
+                    "    // TODO: クエリの形式",
+                    "}",
+                ],
+            ),
+        ];
+        for (input, constraints, expected) in cases {
+            assert_eq!(reading_code(input, constraints), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn keeps_unknown_lines_as_comments() {
+        let code = reading_code(
+            "<pre><var>N</var>\n<var>P_1</var> <var>P_2</var> <var>\\ldots</var> <var>P_{N+1}</var> (1行目)\n</pre>",
+            "",
+        );
+        assert_eq!(code[2..], ["// TODO: P_1 P_2 \\ldots P_{N+1} (1行目)"]);
+    }
+
+    #[test]
+    fn splits_whole_row_vars() {
+        let (tokens, spaced) = split_var("A_{1,1}A_{1,2}\\ldots A_{1,W}");
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(tokens[2], Token::HDots);
+        assert!(!spaced);
+        let (tokens, spaced) = split_var("A_1 A_2 \\ldots A_N");
+        assert_eq!(tokens.len(), 4);
+        assert!(spaced);
+        assert_eq!(split_var("A_{10}").0.len(), 1);
+        assert_eq!(split_var("A_10").0, [parse_token("A_10")]);
+    }
+}
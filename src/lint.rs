@@ -0,0 +1,288 @@
+use crate::bundle;
+
+// ----------------------------------------------  //
+// 提出前のチェック
+// 提出するコードによくある間違いを探す
+// - 標準出力へのデバッグ出力
+// - 有効なままの #define LOCAL
+// - freopen
+// - 展開されていない #include "..."
+// #ifdef LOCAL ... #endif の中や #ifndef LOCAL ... #else 以降はジャッジでは無効になるため調べない
+// ----------------------------------------------  //
+
+pub struct Warning {
+    // 1-indexed の行番号
+    pub line: usize,
+    pub message: String,
+}
+
+pub fn strip_comment(line: &str) -> &str {
+    // 行末の // コメントを取り除く (文字列中の // は考慮しない)
+    line.split_once("//").map_or(line, |(code, _)| code)
+}
+
+pub fn directive(line: &str) -> Option<(&str, &str)> {
+    // "#  ifdef LOCAL" のような行を ("ifdef", "LOCAL") に分ける
+    let rest = line.trim_start().strip_prefix('#')?.trim_start();
+    let end = rest
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(rest.len());
+    Some((&rest[..end], rest[end..].trim()))
+}
+
+pub fn defined_condition(keyword: &str, argument: &str, name: &str) -> Option<bool> {
+    /*
+    #ifdef NAME / #if defined(NAME) なら Some(true)、#ifndef NAME / #if !defined(NAME) なら Some(false)
+    それ以外の条件は None
+    */
+    let argument: String = argument.chars().filter(|c| !c.is_whitespace()).collect();
+    let defined = [format!("defined({})", name), format!("defined{}", name)];
+    match keyword {
+        "ifdef" if argument == name => Some(true),
+        "ifndef" if argument == name => Some(false),
+        "if" if defined.contains(&argument) => Some(true),
+        "if" if argument
+            .strip_prefix('!')
+            .is_some_and(|a| defined.iter().any(|d| d == a)) =>
+        {
+            Some(false)
+        }
+        _ => None,
+    }
+}
+
+fn contains_word(code: &str, word: &str) -> bool {
+    // word が識別子の一部としてではなく現れるか (sprintf の printf などは除く)
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    code.match_indices(word).any(|(i, _)| {
+        !code[..i].ends_with(is_ident) && !code[i + word.len()..].starts_with(is_ident)
+    })
+}
+
+fn is_variable_label(literal: &str) -> bool {
+    // "x = " や "dp[i]: " のように、変数名の後に = か : が続く文字列か
+    let Some(name) = literal
+        .trim()
+        .strip_suffix('=')
+        .or_else(|| literal.trim().strip_suffix(':'))
+    else {
+        return false;
+    };
+    let name = name.trim_end();
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_[].".contains(c))
+}
+
+fn looks_like_debug_output(code: &str) -> bool {
+    // 標準出力に "x = " や "debug" のような文字列を出力しているか
+    let writes_stdout = ["cout", "printf", "puts"]
+        .iter()
+        .any(|w| contains_word(code, w))
+        && !contains_word(code, "cerr");
+    if !writes_stdout {
+        return false;
+    }
+    code.split('"').skip(1).step_by(2).any(|literal| {
+        let lower = literal.to_ascii_lowercase();
+        contains_word(&lower, "debug") || contains_word(&lower, "dbg") || is_variable_label(literal)
+    })
+}
+
+struct LocalBranch {
+    // #ifdef LOCAL なら true、#ifndef LOCAL なら false
+    defined: bool,
+    // #else か #elif より後にいるかどうか
+    in_else: bool,
+}
+
+impl LocalBranch {
+    fn local_only(&self) -> bool {
+        self.defined != self.in_else
+    }
+}
+
+pub fn lint_source(source: &str) -> Vec<Warning> {
+    /*
+    ソースから提出前に直すべき箇所を探す
+    args:
+        source: 提出するソース
+    returns:
+        見つかった問題の一覧
+    */
+    let mut warnings = Vec::new();
+    // #if ごとに、LOCAL が定義されている場合だけ有効な分岐にいるかどうか
+    // (LOCAL で分岐しない #if は None)
+    let mut frames: Vec<Option<LocalBranch>> = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let code = strip_comment(line);
+        let local_only = frames.iter().flatten().any(LocalBranch::local_only);
+        if let Some((keyword, argument)) = directive(code) {
+            match keyword {
+                "if" | "ifdef" | "ifndef" => {
+                    frames.push(
+                        defined_condition(keyword, argument, "LOCAL").map(|defined| LocalBranch {
+                            defined,
+                            in_else: false,
+                        }),
+                    );
+                }
+                // #ifdef LOCAL の #else 側はジャッジで有効になり、#ifndef LOCAL の #else 側は無効になる
+                "else" | "elif" => {
+                    if let Some(Some(branch)) = frames.last_mut() {
+                        branch.in_else = true;
+                    }
+                }
+                "endif" => {
+                    frames.pop();
+                }
+                "define" if !local_only && argument.split_whitespace().next() == Some("LOCAL") => {
+                    warnings.push(Warning {
+                        line: i + 1,
+                        message: "#define LOCAL が有効になっています".to_string(),
+                    });
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if local_only {
+            continue;
+        }
+        if code.contains("freopen(") {
+            warnings.push(Warning {
+                line: i + 1,
+                message: "freopen が残っています".to_string(),
+            });
+        }
+        if looks_like_debug_output(code) {
+            warnings.push(Warning {
+                line: i + 1,
+                message: format!("デバッグ出力が残っている可能性があります: {}", line.trim()),
+            });
+        }
+    }
+    warnings
+}
+
+pub fn unexpanded_includes(bundled: &str) -> Vec<Warning> {
+    /*
+    展開後のソースに残っている #include "..." を探す (ジャッジにはそのファイルがない)
+    */
+    bundled
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let (name, quoted) = bundle::parse_include(line)?;
+            quoted.then(|| Warning {
+                line: i + 1,
+                message: format!("#include \"{}\" が展開されていません", name),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn warned_lines(source: &str) -> Vec<usize> {
+        lint_source(source).iter().map(|w| w.line).collect()
+    }
+
+    #[test]
+    fn parses_defined_conditions() {
+        let cases = [
+            ("ifdef", "LOCAL", Some(true)),
+            ("ifndef", "LOCAL", Some(false)),
+            ("if", "defined(LOCAL)", Some(true)),
+            ("if", "defined LOCAL", Some(true)),
+            ("if", "! defined ( LOCAL )", Some(false)),
+            ("ifdef", "LOCAL_MAX", None),
+            ("if", "defined(LOCAL) && X", None),
+            ("if", "LOCAL", None),
+        ];
+        for (keyword, argument, expected) in cases {
+            assert_eq!(
+                defined_condition(keyword, argument, "LOCAL"),
+                expected,
+                "#{} {}",
+                keyword,
+                argument
+            );
+        }
+    }
+
+    #[test]
+    fn skips_local_only_branches() {
+        let debug = "cout << \"debug\" << endl;";
+        let cases = [
+            (format!("#ifdef LOCAL\n{}\n#endif\n", debug), vec![]),
+            (format!("#ifdef LOCAL\n#else\n{}\n#endif\n", debug), vec![3]),
+            (format!("#ifndef LOCAL\n{}\n#endif\n", debug), vec![2]),
+            (format!("#ifndef LOCAL\n#else\n{}\n#endif\n", debug), vec![]),
+            (
+                format!("#if !defined(LOCAL)\n#else\n{}\n#endif\n", debug),
+                vec![],
+            ),
+            (
+                format!("#ifdef LOCAL\n#elif X\n{}\n#endif\n", debug),
+                vec![3],
+            ),
+            (
+                format!("#ifndef LOCAL\n#elif X\n{}\n#endif\n", debug),
+                vec![],
+            ),
+            // LOCAL で分岐しない #if の中は調べる
+            (
+                format!("#ifdef LOCAL\n#if X\n#else\n{}\n#endif\n#endif\n", debug),
+                vec![],
+            ),
+            (
+                format!("#if X\n#ifdef LOCAL\n#endif\n#else\n{}\n#endif\n", debug),
+                vec![5],
+            ),
+            (format!("#ifdef LOCAL\n#endif\n{}\n", debug), vec![3]),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(warned_lines(&source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn finds_submission_mistakes() {
+        let cases = [
+            ("#define LOCAL", vec![1]),
+            ("#define LOCAL_MAX 10", vec![]),
+            ("#ifdef LOCAL\n#define LOCAL\n#endif", vec![]),
+            ("freopen(\"in.txt\", \"r\", stdin);", vec![1]),
+            ("cout << \"x = \" << x << endl;", vec![1]),
+            ("std::cout << \"dp[i]: \" << dp[i] << '\\n';", vec![1]),
+            ("printf(\"[debug] %d\\n\", x);", vec![1]),
+            ("cout << \"DBG \" << x;", vec![1]),
+            // デバッグ出力に見えない出力や標準エラー出力への出力
+            ("cout << \"Case #\" << t << \": \" << ans << endl;", vec![]),
+            ("cout << (a == b ? \"Yes\" : \"No\") << endl;", vec![]),
+            ("cerr << \"x = \" << x << endl;", vec![]),
+            ("fprintf(stderr, \"x = %d\\n\", x);", vec![]),
+            ("sprintf(buf, \"x = %d\", x);", vec![]),
+            ("cout << \"debugging is fun\" << endl;", vec![]),
+            ("cout << x << endl; // debug", vec![]),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(warned_lines(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn finds_unexpanded_includes() {
+        let bundled = "#include <bits/stdc++.h>\n#include \"lib.hpp\"\nint main() {}\n";
+        let lines: Vec<usize> = unexpanded_includes(bundled)
+            .iter()
+            .map(|w| w.line)
+            .collect();
+        assert_eq!(lines, vec![2]);
+    }
+}
@@ -1,17 +1,35 @@
+mod acl;
+mod ahc;
+mod ahc_gallery;
+mod ahc_history;
+mod bundle;
+mod clip;
+mod compile;
+mod config;
+mod diff;
+mod exec;
+mod generator;
+mod input_format;
+mod lint;
+mod report;
+mod strip;
+
 use anyhow::Ok;
 use clap::{Parser, Subcommand};
 use colored::Colorize;
-use dialoguer::Select;
 use dialoguer::console::style;
+use dialoguer::{Confirm, Select};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use report::{CaseResult, Reporter, Verdict};
 use reqwest::Client;
 use scraper::{Html, Selector};
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{
     env::{self, current_dir},
     process::Stdio,
-    u32,
 };
 
 const CPP_TEMPLATE: &str = r#"#include <iostream>
@@ -27,7 +45,6 @@ int main() {
 // ----------------------------------------------  //
 // 以下TODO
 // current dirがahc の場合に限りビジュアライザへのリンクを表示する
-//　atk test xx メソッドをはやす。current dirがahc の時に限り, ./a.out < output.txt > input_xx.txt を実行する.
 // ----------------------------------------------  //
 
 #[derive(Parser, Debug)]
@@ -45,13 +62,195 @@ struct Cli {
 #[derive(Subcommand, Debug)]
 enum Commands {
     New { contest_name: String },
-    Test { problem_char: String },
-    Copy { problem_char: String },
+    Test {
+        #[arg(required_unless_present = "all")]
+        problem_char: Option<String>,
+        /// コンテストの全ての問題をテストし、結果を表にまとめる
+        #[arg(long, conflicts_with_all = ["problem_char", "watch", "format"])]
+        all: bool,
+        /// ソースファイルの変更を監視し、保存のたびにテストを再実行する
+        #[arg(long)]
+        watch: bool,
+        /// テストケースを1つずつ順番に実行する (実行時間を正確に測りたい場合)
+        #[arg(long)]
+        serial: bool,
+        /// サニタイザと -DLOCAL を有効にしてビルドし、全てのケースの標準エラー出力を表示する
+        #[arg(long)]
+        debug: bool,
+        /// WA のとき、出力を省略せずに全ての行を表示する
+        #[arg(long)]
+        full_diff: bool,
+        /// 実行時間制限 (ms)。超えたケースは TLE とし、プロセスを止める
+        #[arg(long, default_value_t = DEFAULT_TIME_LIMIT_MS)]
+        time_limit: u64,
+        /// 結果の出力形式
+        #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+        format: OutputFormat,
+        /// 実行するケース (例: --case 3, --case 2..5, --case gen_1)。複数指定できる
+        #[arg(long = "case", value_parser = parse_case_filter)]
+        cases: Vec<CaseFilter>,
+        /// 自分で追加したケース (.test/<problem>/ のサンプル以外) だけを実行する
+        #[arg(long)]
+        only_custom: bool,
+    },
+    Run {
+        problem_char: String,
+        /// 標準入力として与えるファイル (省略時は端末から入力する)
+        #[arg(long)]
+        input: Option<PathBuf>,
+        /// atk test --debug と同じフラグでビルドする
+        #[arg(long)]
+        debug: bool,
+        /// 実行時間制限 (ms)。省略時は制限しない
+        #[arg(long)]
+        time_limit: Option<u64>,
+    },
+    Copy {
+        problem_char: String,
+        /// #include <atcoder/...> を展開してからコピーする
+        #[arg(long)]
+        expand_acl: bool,
+        /// 提出前のチェックを行わずにコピーする
+        #[arg(long)]
+        force: bool,
+    },
+    Gen {
+        problem_char: String,
+        /// 仕様ファイル (省略時は <problem>.gen)
+        #[arg(long)]
+        spec: Option<PathBuf>,
+        #[arg(long)]
+        seed: Option<u64>,
+        #[arg(long, default_value_t = 1)]
+        count: u64,
+        /// .test/<problem>/gen_in<n>.txt と期待出力の gen_out<n>.txt に保存する
+        #[arg(long)]
+        save: bool,
+        /// --save で期待出力を作る解答 (省略時は <problem>.cpp)
+        #[arg(long, requires = "save")]
+        naive: Option<PathBuf>,
+    },
+    Pch {
+        #[command(subcommand)]
+        command: PchCommands,
+    },
+    Acl {
+        #[command(subcommand)]
+        command: AclCommands,
+    },
+    Ahc {
+        #[command(subcommand)]
+        command: AhcCommands,
+    },
+    Stress {
+        problem_char: String,
+        /// 比較対象の愚直解
+        #[arg(long)]
+        naive: PathBuf,
+        #[arg(long)]
+        spec: Option<PathBuf>,
+        #[arg(long)]
+        seed: Option<u64>,
+        #[arg(long, default_value_t = 100)]
+        count: u64,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PchCommands {
+    /// 設定されたヘッダをプリコンパイルする
+    Build,
+    /// プリコンパイル済みヘッダを削除する
+    Clean,
+}
+
+#[derive(Subcommand, Debug)]
+enum AhcCommands {
+    /// 公式のローカルツールを tools/ に展開してビルドし、in/ に入力を生成する
+    Tools {
+        /// 手元にある zip (省略時は問題ページのリンクからダウンロードする)
+        archive: Option<PathBuf>,
+    },
+    /// in/ の入力で main.cpp を並列に実行し、公式ツールで得点を計算する
+    Run {
+        /// 実行するシード (例: 0..100 で 0 から 99 まで)。省略時は in/ の全ての入力
+        #[arg(long, value_parser = ahc::parse_seeds)]
+        seeds: Option<std::ops::Range<u32>>,
+        /// シードごとの実行時間制限 (ms)
+        #[arg(long, default_value_t = DEFAULT_TIME_LIMIT_MS)]
+        time_limit: u64,
+        /// シードを1つずつ順番に実行する
+        #[arg(long)]
+        serial: bool,
+        /// 履歴に残す説明 (例: -m "焼きなましの温度を変更")
+        #[arg(short = 'm', long)]
+        label: Option<String>,
+    },
+    /// out/ の出力を vis で可視化し、シードごとの結果を1つの HTML にまとめる
+    Vis {
+        /// 表示するシード (例: 0..10)。省略時は out/ の全ての出力
+        #[arg(long, value_parser = ahc::parse_seeds)]
+        seeds: Option<std::ops::Range<u32>>,
+    },
+    /// atk ahc run の履歴を表示する
+    History,
+    /// 複数の解答や実行を、公式のシステムテストと同じ相対評価で順位付けする
+    Rank {
+        /// 比べる実行の番号 (省略時は --solution で実行したものと履歴の全て)
+        runs: Vec<usize>,
+        /// 新たに実行して比べる解答 (.cpp はコンパイルし、それ以外は実行ファイルとして扱う)。複数指定できる
+        #[arg(long = "solution")]
+        solutions: Vec<PathBuf>,
+        /// --solution を実行するシード (例: 0..100)
+        #[arg(long, value_parser = ahc::parse_seeds)]
+        seeds: Option<std::ops::Range<u32>>,
+        /// シードごとの実行時間制限 (ms)
+        #[arg(long, default_value_t = DEFAULT_TIME_LIMIT_MS)]
+        time_limit: u64,
+        /// 得点を最大化するか最小化するか (省略時は .atk/ahc.toml の objective)
+        #[arg(long, value_enum)]
+        objective: Option<ahc::Objective>,
+    },
+    /// 2回分の atk ahc run の結果をシードごとに比べる
+    Compare {
+        /// 基準にする実行の番号 (省略時は最後から2番目)
+        base: Option<usize>,
+        /// 比べる実行の番号 (省略時は最後)
+        target: Option<usize>,
+        /// 得点を最大化するか最小化するか (省略時は .atk/ahc.toml の objective)
+        #[arg(long, value_enum)]
+        objective: Option<ahc::Objective>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AclCommands {
+    /// ACL の zip を展開してインクルードパスに加える
+    Install {
+        /// 手元にある zip (省略時は公式のリリースをダウンロードする)
+        archive: Option<PathBuf>,
+    },
+    /// #include <atcoder/...> を展開したソースを出力する
+    Expand {
+        problem_char: String,
+        /// 出力先 (省略時は標準出力)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    // 設定ファイルの誤りで設定を使わないコマンドまで動かなくならないよう、既定値で続ける
+    let config = config::load().unwrap_or_else(|e| {
+        eprintln!(
+            "{} {} (既定の設定を使います)",
+            "warning:".yellow().bold(),
+            e
+        );
+        config::Config::default()
+    });
 
     match cli.command {
         Commands::New { contest_name } => {
@@ -63,21 +262,287 @@ async fn main() -> anyhow::Result<()> {
             } else if contest_id == "typical90" {
                 create_typical90_contest_directory(contest_id)?;
             } else {
-                create_algorithm_contest_directory(contest_id)?;
+                create_algorithm_contest_directory(contest_id).await?;
             }
         }
-        Commands::Test { problem_char } => {
+        Commands::Test {
+            problem_char,
+            all,
+            watch,
+            serial,
+            debug,
+            full_diff,
+            time_limit,
+            format,
+            cases,
+            only_custom,
+        } => {
             let current_path = env::current_dir()?;
             let contest_id = extract_contest_id_from_path(&current_path)?;
-            run_tests(contest_id, problem_char).await?;
+            let options = TestOptions {
+                serial,
+                debug,
+                full_diff,
+                time_limit: std::time::Duration::from_millis(time_limit),
+                format,
+                cases,
+                only_custom,
+            };
+            if all {
+                let code = test_all(contest_id, &options, &config).await?;
+                std::process::exit(code);
+            }
+            // --all でない場合は clap が problem_char を必須にしている
+            let problem_char = problem_char.unwrap_or_default();
+            if watch {
+                watch_tests(contest_id, problem_char, &options, &config).await?;
+            } else {
+                let mut reporter = make_reporter(&problem_char, &options);
+                let results = match run_tests(
+                    contest_id,
+                    problem_char,
+                    &options,
+                    &config,
+                    reporter.as_mut(),
+                )
+                .await
+                {
+                    Err(e) if e.is::<compile::CompileError>() => {
+                        eprintln!("Error: {}", e);
+                        reporter.compile_error(compile_message(&e));
+                        std::process::exit(EXIT_COMPILE_ERROR);
+                    }
+                    results => results?,
+                };
+                std::process::exit(exit_code(&results));
+            }
+        }
+        Commands::Run {
+            problem_char,
+            input,
+            debug,
+            time_limit,
+        } => {
+            let time_limit = time_limit.map(std::time::Duration::from_millis);
+            let code =
+                match run_program(&problem_char, input.as_deref(), debug, time_limit, &config) {
+                    Err(e) if e.is::<compile::CompileError>() => {
+                        eprintln!("Error: {}", e);
+                        EXIT_COMPILE_ERROR
+                    }
+                    code => code?,
+                };
+            if code != 0 {
+                std::process::exit(code);
+            }
         }
-        Commands::Copy { problem_char } => {
+        Commands::Copy {
+            problem_char,
+            expand_acl,
+            force,
+        } => {
             let current_path = env::current_dir()?;
             println!("現在のディレクトリ: {}", current_path.display());
             let contest_id = extract_contest_id_from_path(&current_path)?;
             println!("コンテストID: {}", contest_id);
-            copy_problem_template(&contest_id, problem_char)?;
+            copy_problem_template(&contest_id, problem_char, expand_acl, force, &config).await?;
+        }
+        Commands::Gen {
+            problem_char,
+            spec,
+            seed,
+            count,
+            save,
+            naive,
+        } => {
+            generate_inputs(&problem_char, spec, seed, count, save, naive, &config)?;
+        }
+        Commands::Stress {
+            problem_char,
+            naive,
+            spec,
+            seed,
+            count,
+        } => {
+            stress_test(&problem_char, &naive, spec, seed, count, &config)?;
         }
+        Commands::Pch { command } => match command {
+            PchCommands::Build => {
+                let dir = compile::build_pch(&config)?;
+                println!(
+                    "{} プリコンパイル済みヘッダを {} に作成しました",
+                    style("finished").green(),
+                    dir.display()
+                );
+            }
+            PchCommands::Clean => {
+                compile::clean_pch()?;
+                println!("プリコンパイル済みヘッダを削除しました");
+            }
+        },
+        Commands::Ahc { command } => match command {
+            AhcCommands::Tools { archive } => {
+                let contest_dir = ahc_contest_dir()?;
+                let source = match archive {
+                    Some(path) => ahc::ToolsSource::Archive(path),
+                    None => {
+                        let contest_id = extract_contest_id_from_path(&contest_dir)?;
+                        let url = format!(
+                            "https://atcoder.jp/contests/{}/tasks/{}_a",
+                            contest_id, contest_id
+                        );
+                        let document = fetch_task_page(&url).await?;
+                        let archives = ahc::tool_archives(&ahc_tool_links(&document, &contest_id));
+                        if archives.is_empty() {
+                            return Err(anyhow::anyhow!(
+                                "問題ページにローカル版のツールが見つかりません"
+                            ));
+                        }
+                        ahc::ToolsSource::Urls(archives)
+                    }
+                };
+                let meta = ahc::install_tools(&contest_dir, source).await?;
+                for (name, path) in [("vis", &meta.vis), ("tester", &meta.tester)] {
+                    if let Some(path) = path {
+                        println!("{}: {}", name, path.display());
+                    }
+                }
+                println!("{} 公式ツールを準備しました", style("finished").green());
+            }
+            AhcCommands::Run {
+                seeds,
+                time_limit,
+                serial,
+                label,
+            } => {
+                let contest_dir = ahc_contest_dir()?;
+                let source = contest_dir.join("main.cpp");
+                let version = ahc_history::source_version(&contest_dir, &source)?;
+                let executable = compile::compile_cpp(&source, &config, false)?;
+                let options = ahc_run_options(seeds, time_limit, serial, false);
+                let results = ahc::run(&contest_dir, &executable, &options)?;
+                let record = ahc_history::record_run(&contest_dir, version, label, &results)?;
+                println!(
+                    "{}",
+                    format!("実行 #{} として記録しました", record.id).dimmed()
+                );
+            }
+            AhcCommands::Rank {
+                runs,
+                solutions,
+                seeds,
+                time_limit,
+                objective,
+            } => {
+                let contest_dir = ahc_contest_dir()?;
+                let options = ahc_run_options(seeds, time_limit, false, true);
+                let mut ids = runs;
+                for solution in &solutions {
+                    if !solution.exists() {
+                        return Err(anyhow::anyhow!("{} が存在しません", solution.display()));
+                    }
+                    let version = ahc_history::source_version(&contest_dir, solution)?;
+                    let executable = if solution.extension().is_some_and(|e| e == "cpp") {
+                        compile::compile_cpp(solution, &config, false)?
+                    } else {
+                        solution.canonicalize()?
+                    };
+                    println!("{} を実行しています...", solution.display());
+                    let results = ahc::run(&contest_dir, &executable, &options)?;
+                    let record = ahc_history::record_run(
+                        &contest_dir,
+                        version,
+                        Some(solution.display().to_string()),
+                        &results,
+                    )?;
+                    ids.push(record.id);
+                }
+
+                let all_runs = ahc_history::load_runs(&contest_dir)?;
+                let selected = if ids.is_empty() {
+                    all_runs.iter().collect()
+                } else {
+                    ids.iter()
+                        .map(|&id| ahc_history::find_run(&all_runs, id))
+                        .collect::<anyhow::Result<Vec<_>>>()?
+                };
+                let objective = match objective {
+                    Some(objective) => objective,
+                    None => ahc::ContestMeta::load(&contest_dir)?.objective,
+                };
+                ahc_history::rank(&selected, objective)?;
+            }
+            AhcCommands::Vis { seeds } => {
+                let contest_dir = ahc_contest_dir()?;
+                let jobs = std::thread::available_parallelism().map_or(1, |n| n.get());
+                let path = ahc_gallery::render(&contest_dir, seeds, jobs)?;
+                println!(
+                    "{} file://{} を作成しました",
+                    style("finished").green(),
+                    path.display()
+                );
+            }
+            AhcCommands::History => {
+                let runs = ahc_history::load_runs(&ahc_contest_dir()?)?;
+                if runs.is_empty() {
+                    println!("まだ atk ahc run の履歴がありません");
+                }
+                ahc_history::print_runs(&runs);
+            }
+            AhcCommands::Compare {
+                base,
+                target,
+                objective,
+            } => {
+                let contest_dir = ahc_contest_dir()?;
+                let runs = ahc_history::load_runs(&contest_dir)?;
+                let last = runs.last().map_or(0, |run| run.id);
+                let target = target.unwrap_or(last);
+                let base = match base {
+                    Some(base) => base,
+                    // 最後の実行と、その1つ前の実行を比べる
+                    None => runs
+                        .iter()
+                        .rev()
+                        .find(|run| run.id < target)
+                        .map(|run| run.id)
+                        .ok_or_else(|| anyhow::anyhow!("比べる実行が2回分ありません"))?,
+                };
+                let objective = match objective {
+                    Some(objective) => objective,
+                    None => ahc::ContestMeta::load(&contest_dir)?.objective,
+                };
+                ahc_history::compare(
+                    ahc_history::find_run(&runs, base)?,
+                    ahc_history::find_run(&runs, target)?,
+                    objective,
+                );
+            }
+        },
+        Commands::Acl { command } => match command {
+            AclCommands::Install { archive } => {
+                let dir = acl::install(archive, &config).await?;
+                println!(
+                    "{} ACL を {} に展開しました",
+                    style("finished").green(),
+                    dir.display()
+                );
+            }
+            AclCommands::Expand {
+                problem_char,
+                output,
+            } => {
+                let file_path = PathBuf::from(format!("{}.cpp", problem_char));
+                if !file_path.exists() {
+                    return Err(anyhow::anyhow!("{} が存在しません", file_path.display()));
+                }
+                let expanded = bundle_source(&file_path, true, &config)?;
+                match output {
+                    Some(output) => fs::write(output, expanded)?,
+                    None => print!("{}", expanded),
+                }
+            }
+        },
     }
     return Ok(());
 }
@@ -113,14 +578,14 @@ fn format_contest_id(input_id: &String) -> anyhow::Result<String> {
         ));
     }
 
-    let (prefix, num_str) = if input_id.starts_with("abc") {
-        ("abc", &input_id[3..])
-    } else if input_id.starts_with("arc") {
-        ("arc", &input_id[3..])
-    } else if input_id.starts_with("agc") {
-        ("agc", &input_id[3..])
-    } else if input_id.starts_with("ahc") {
-        ("ahc", &input_id[3..])
+    let (prefix, num_str) = if let Some(num_str) = input_id.strip_prefix("abc") {
+        ("abc", num_str)
+    } else if let Some(num_str) = input_id.strip_prefix("arc") {
+        ("arc", num_str)
+    } else if let Some(num_str) = input_id.strip_prefix("agc") {
+        ("agc", num_str)
+    } else if let Some(num_str) = input_id.strip_prefix("ahc") {
+        ("ahc", num_str)
     } else {
         return Err(anyhow::anyhow!(
             "コンテストIDはabc, arc, agc, ahc で始まるか edpc である必要があります"
@@ -131,10 +596,10 @@ fn format_contest_id(input_id: &String) -> anyhow::Result<String> {
         .parse::<u32>()
         .map_err(|_| anyhow::anyhow!("コンテスト番号は数字でなければなりません"))?;
 
-    return Ok(format!("{}{:03}", prefix, contest_num));
+    Ok(format!("{}{:03}", prefix, contest_num))
 }
 
-fn extract_contest_id_from_path(path: &PathBuf) -> anyhow::Result<String> {
+fn extract_contest_id_from_path(path: &Path) -> anyhow::Result<String> {
     /*
     現在のディレクトリ名が正しいパスであることを確認する
     args:
@@ -163,8 +628,48 @@ fn extract_contest_id_from_path(path: &PathBuf) -> anyhow::Result<String> {
     }
 }
 
-fn create_algorithm_contest_directory(contest_name: String) -> anyhow::Result<()> {
-    let contest_dir = PathBuf::from(contest_name);
+fn ahc_run_options(
+    seeds: Option<std::ops::Range<u32>>,
+    time_limit: u64,
+    serial: bool,
+    quiet: bool,
+) -> ahc::RunOptions {
+    ahc::RunOptions {
+        seeds,
+        time_limit: std::time::Duration::from_millis(time_limit),
+        jobs: if serial {
+            1
+        } else {
+            std::thread::available_parallelism().map_or(1, |n| n.get())
+        },
+        quiet,
+    }
+}
+
+fn ahc_contest_dir() -> anyhow::Result<PathBuf> {
+    /*
+    現在のディレクトリが AHC のコンテストのディレクトリであることを確認する
+    returns:
+        コンテストのディレクトリ
+    */
+    let current_path = env::current_dir()?;
+    let contest_id = extract_contest_id_from_path(&current_path)?;
+    if !contest_id.starts_with("ahc") {
+        return Err(anyhow::anyhow!(
+            "atk ahc は ahcxxx のディレクトリで実行してください"
+        ));
+    }
+    if !current_path.join("main.cpp").exists() {
+        return Err(anyhow::anyhow!(
+            "{} が存在しません",
+            current_path.join("main.cpp").display()
+        ));
+    }
+    Ok(current_path)
+}
+
+async fn create_algorithm_contest_directory(contest_name: String) -> anyhow::Result<()> {
+    let contest_dir = PathBuf::from(&contest_name);
 
     println!("{} を作成しますか?", contest_dir.display());
     let choices = &["yes", "no"];
@@ -185,6 +690,7 @@ fn create_algorithm_contest_directory(contest_name: String) -> anyhow::Result<()
     let problems = ['a', 'b', 'c', 'd', 'e', 'f', 'g'];
 
     let mut exist_files: Vec<String> = Vec::new();
+    let mut unfetched: Vec<String> = Vec::new();
 
     for problem in problems {
         let file_name = format!("{}.cpp", problem);
@@ -195,9 +701,40 @@ fn create_algorithm_contest_directory(contest_name: String) -> anyhow::Result<()
             continue;
         }
 
+        // 問題ページから入力形式を推定できた場合は読み込みコードを書き込む
+        let url = format!(
+            "https://atcoder.jp/contests/{}/tasks/{}_{}",
+            contest_name, contest_name, problem
+        );
+        let source = match fetch_task_page(&url).await.ok() {
+            Some(document) => input_format::parse_input_format(&document)
+                .map(|format| format.render(CPP_TEMPLATE))
+                .unwrap_or_else(|| CPP_TEMPLATE.to_string()),
+            None => {
+                unfetched.push(format!("{}.cpp", problem));
+                CPP_TEMPLATE.to_string()
+            }
+        };
+
         let mut file = fs::File::create(&file_path)?;
 
-        file.write_all(CPP_TEMPLATE.as_bytes())?;
+        file.write_all(source.as_bytes())?;
+
+        // atk test --all で未着手のファイルを見分けるため、生成した内容を残しておく
+        if source != CPP_TEMPLATE {
+            let template_path = contest_dir
+                .join(TEMPLATE_DIR)
+                .join(format!("{}.cpp", problem));
+            fs::create_dir_all(contest_dir.join(TEMPLATE_DIR))?;
+            fs::write(template_path, &source)?;
+        }
+    }
+
+    if !unfetched.is_empty() {
+        println!(
+            "{} は問題ページを取得できなかったため、テンプレートのみを書き込みました",
+            unfetched.join(" ")
+        );
     }
 
     let test_dir = contest_dir.join(".test");
@@ -220,7 +757,7 @@ fn create_algorithm_contest_directory(contest_name: String) -> anyhow::Result<()
         style("finished").green(),
         contest_dir.display()
     );
-    return Ok(());
+    Ok(())
 }
 
 async fn create_heuristic_contest_directory(contest_name: String) -> anyhow::Result<()> {
@@ -261,41 +798,58 @@ async fn create_heuristic_contest_directory(contest_name: String) -> anyhow::Res
         &contest_name, &contest_name
     );
     // https://atcoder.jp/contests/ahc048/tasks/ahc048_a
-    let client = Client::new();
-    let res = client.get(&url).send().await?;
-    if !res.status().is_success() {
-        return Err(anyhow::anyhow!(
-            "HTTPリクエストが失敗しました: {}",
-            res.status()
-        ));
+    let document = fetch_task_page(&url).await?;
+    let links = ahc_tool_links(&document, &contest_name);
+    for link in &links {
+        println!("ビジュアライザ画像リンク: {}", link);
     }
 
-    let body = res.text().await?;
-    let document = Html::parse_document(&body);
+    // ローカル版のツール (zip) があれば展開してビルドする
+    let archives = ahc::tool_archives(&links);
+    if !archives.is_empty()
+        && let Err(e) = ahc::install_tools(&contest_dir, ahc::ToolsSource::Urls(archives)).await
+    {
+        eprintln!(
+            "{} 公式ツールの準備に失敗しました: {} (atk ahc tools で再実行できます)",
+            "warning:".yellow().bold(),
+            e
+        );
+    }
 
+    Ok(())
+}
+
+fn ahc_tool_links(document: &Html, contest_name: &str) -> Vec<String> {
+    /*
+    問題ページからビジュアライザやローカル版のツールへのリンクを集める
+    args:
+        document: 問題ページのHTML
+        contest_name: コンテスト名 (例: "ahc048")
+    returns:
+        リンクのURL
+    */
     let a_selector = Selector::parse("a").unwrap();
+    let mut links = Vec::new();
 
-    let mut a_s = document.select(&a_selector).peekable();
-    while let Some(a) = a_s.next() {
-        if let Some(href) = a.value().attr("href") {
-            if href.starts_with(&format!("/ahc{}/", &contest_name[3..]))
-                || href.contains(&format!("img.atcoder.jp/ahc{}", &contest_name[3..]))
-            {
-                let url = if href.starts_with("http") {
-                    href.to_string()
-                } else {
-                    format!(
-                        "https://img.atcoder.jp/ahc{}/{}",
-                        &contest_name[3..],
-                        href.trim_start_matches(&format!("/ahc{}/", &contest_name[3..]))
-                    )
-                };
-                println!("ビジュアライザ画像リンク: {}", url);
-            }
+    for a in document.select(&a_selector) {
+        if let Some(href) = a.value().attr("href")
+            && (href.starts_with(&format!("/ahc{}/", &contest_name[3..]))
+                || href.contains(&format!("img.atcoder.jp/ahc{}", &contest_name[3..])))
+        {
+            let url = if href.starts_with("http") {
+                href.to_string()
+            } else {
+                format!(
+                    "https://img.atcoder.jp/ahc{}/{}",
+                    &contest_name[3..],
+                    href.trim_start_matches(&format!("/ahc{}/", &contest_name[3..]))
+                )
+            };
+            links.push(url);
         }
     }
 
-    return Ok(());
+    links
 }
 
 fn create_edpc_contest_directory(contest_name: String) -> anyhow::Result<()> {
@@ -361,7 +915,7 @@ fn create_edpc_contest_directory(contest_name: String) -> anyhow::Result<()> {
         contest_dir.display()
     );
 
-    return Ok(());
+    Ok(())
 }
 
 fn create_typical90_contest_directory(contest_name: String) -> anyhow::Result<()> {
@@ -427,15 +981,41 @@ fn create_typical90_contest_directory(contest_name: String) -> anyhow::Result<()
         contest_dir.display()
     );
 
-    return Ok(());
+    Ok(())
 }
 
 #[derive(Debug)]
 struct TestCase {
+    // サンプルは "1", "2", ...、自分で追加したケースは "gen_1" のようなファイル名から決まる
+    id: String,
+    // サンプル以外のケース
+    custom: bool,
     input: String,
     output: String,
 }
 
+async fn fetch_task_page(url: &str) -> anyhow::Result<Html> {
+    /*
+    問題ページを取得してHTMLとして解釈する
+    args:
+        url: 問題ページのURL
+    returns:
+        問題ページのHTML
+    */
+    let client = Client::new();
+    let res = client.get(url).send().await?;
+    if !res.status().is_success() {
+        return Err(anyhow::anyhow!(
+            "HTTPリクエストが失敗しました: {}",
+            res.status()
+        ));
+    }
+
+    let body = res.text().await?;
+
+    Ok(Html::parse_document(&body))
+}
+
 async fn get_sample_cases(
     contest_id: String,
     problem_char: &String,
@@ -469,37 +1049,25 @@ async fn get_sample_cases(
         contest_id, contest_id, problem_char
     )};
 
-    println!("{}", url);
-
-    let client = Client::new();
-    let res = client.get(&url).send().await?;
-    if !res.status().is_success() {
-        return Err(anyhow::anyhow!(
-            "HTTPリクエストが失敗しました: {}",
-            res.status()
-        ));
-    }
-
-    let body = res.text().await?;
+    eprintln!("{}", url);
 
-    let document = Html::parse_document(&body);
+    let document = fetch_task_page(&url).await?;
 
     let h3_selector = Selector::parse("h3").unwrap();
     let pre_selector = Selector::parse("pre").unwrap();
 
     let mut samples = Vec::new();
-    let mut h3s = document.select(&h3_selector).peekable();
     let mut pre_iter = document.select(&pre_selector);
     let mut inputs = Vec::new();
     let mut outputs = Vec::new();
 
     // h3タグを順に見ていき、入力例/出力例の直後のpreを取得
-    while let Some(h3) = h3s.next() {
+    for h3 in document.select(&h3_selector) {
         let h3_text = h3.text().collect::<String>();
         if h3_text.contains("入力例") {
             if let Some(pre) = h3
                 .next_sibling()
-                .and_then(|n| scraper::ElementRef::wrap(n))
+                .and_then(scraper::ElementRef::wrap)
                 .filter(|e| e.value().name() == "pre")
             {
                 inputs.push(pre.text().collect::<Vec<_>>().join(""));
@@ -510,7 +1078,7 @@ async fn get_sample_cases(
         } else if h3_text.contains("出力例") {
             if let Some(pre) = h3
                 .next_sibling()
-                .and_then(|n| scraper::ElementRef::wrap(n))
+                .and_then(scraper::ElementRef::wrap)
                 .filter(|e| e.value().name() == "pre")
             {
                 outputs.push(pre.text().collect::<Vec<_>>().join(""));
@@ -522,6 +1090,8 @@ async fn get_sample_cases(
     let n = std::cmp::min(inputs.len(), outputs.len());
     for i in 0..n {
         samples.push(TestCase {
+            id: (i + 1).to_string(),
+            custom: false,
             input: inputs[i].clone(),
             output: outputs[i].clone(),
         });
@@ -530,6 +1100,58 @@ async fn get_sample_cases(
     Ok(samples)
 }
 
+fn split_case_name(stem: &str) -> Option<(&str, &str)> {
+    /*
+    入力ファイルの名前 (拡張子を除く) を、in の前後に分ける
+    "in1" は ("", "1")、"gen_in1" は ("gen_", "1")
+    名前の途中の in (join_in1 の join) と区別するため、in は先頭か _ の直後のものに限る
+    */
+    if let Some(pos) = stem.rfind("_in") {
+        return Some((&stem[..pos + 1], &stem[pos + 3..]));
+    }
+    stem.strip_prefix("in").map(|suffix| ("", suffix))
+}
+
+fn load_cached_cases(test_dir: &Path) -> anyhow::Result<Vec<TestCase>> {
+    /*
+    .test/<problem>/ に保存されたテストケースを読み込む
+    in<n>.txt と out<n>.txt、<name>_in<n>.txt と <name>_out<n>.txt の組を1つのケースとし、
+    in<数字>.txt をサンプル、それ以外 (gen_in1.txt など) を自分で追加したケースとして扱う
+    returns:
+        サンプル (番号順)、自分で追加したケース (名前順) の順に並べたリスト
+    */
+    let mut cases = Vec::new();
+    for entry in fs::read_dir(test_dir)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        let Some((prefix, suffix)) = name.strip_suffix(".txt").and_then(split_case_name) else {
+            continue;
+        };
+        let out_path = test_dir.join(format!("{}out{}.txt", prefix, suffix));
+        if !out_path.exists() {
+            continue;
+        }
+        let custom = !prefix.is_empty() || suffix.parse::<u32>().is_err();
+        cases.push(TestCase {
+            id: format!("{}{}", prefix, suffix),
+            custom,
+            input: fs::read_to_string(test_dir.join(&name))?,
+            output: fs::read_to_string(&out_path)?,
+        });
+    }
+    // 番号部分は数値として比べる (gen_2 < gen_10)
+    let sort_key = |case: &TestCase| {
+        let digits = case.id.len() - case.id.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+        let (name, number) = case.id.split_at(case.id.len() - digits);
+        (
+            case.custom,
+            name.to_string(),
+            number.parse::<u64>().unwrap_or(0),
+        )
+    };
+    cases.sort_by_key(sort_key);
+    Ok(cases)
+}
+
 async fn get_sample_cases_cached (
     contest_id: String,
     problem_char: &String,
@@ -540,50 +1162,212 @@ async fn get_sample_cases_cached (
         contest_id: コンテストID (例: "abc123")
         problem_char: 問題文字 (例: "a", "b", "c" など)
     returns:
-        サンプルケースのリスト
+        サンプルケースと自分で追加したケースのリスト (各ケースは id を持つ)
     */
 
     // テストケース保存ディレクトリ
     let test_dir = PathBuf::from(".test").join(problem_char);
-    if test_dir.exists() {
-        // 既存のテストケースを読み込む
-        let mut cases = Vec::new();
-        let mut idx = 1;
-        loop {
-            let in_path = test_dir.join(format!("in{}.txt", idx));
-            let out_path = test_dir.join(format!("out{}.txt", idx));
-            if !in_path.exists() || !out_path.exists() {
-                break;
-            }
-            let input = fs::read_to_string(&in_path)?;
-            let output = fs::read_to_string(&out_path)?;
-            cases.push(TestCase { input, output });
-            idx += 1;
-        }
-        if !cases.is_empty() {
-            return Ok(cases);
-        }
+    let mut cases = if test_dir.exists() {
+        load_cached_cases(&test_dir)?
+    } else {
+        Vec::new()
+    };
+    if cases.iter().any(|case| !case.custom) {
+        return Ok(cases);
     }
-    // なければWebから取得し保存
-    let cases = get_sample_cases(contest_id, problem_char).await?;
-    if !cases.is_empty() {
+    // サンプルがなければWebから取得し保存
+    let samples = get_sample_cases(contest_id, problem_char).await?;
+    if !samples.is_empty() {
         fs::create_dir_all(&test_dir)?;
-        for (i, case) in cases.iter().enumerate() {
-            let in_path = test_dir.join(format!("in{}.txt", i + 1));
-            let out_path = test_dir.join(format!("out{}.txt", i + 1));
+        for case in &samples {
+            let in_path = test_dir.join(format!("in{}.txt", case.id));
+            let out_path = test_dir.join(format!("out{}.txt", case.id));
             fs::write(in_path, &case.input)?;
             fs::write(out_path, &case.output)?;
         }
     }
+    cases.splice(0..0, samples);
     Ok(cases)
 }
 
-async fn run_tests(contest_id: String, problem_char: String) -> anyhow::Result<()> {
+#[derive(Debug, Clone)]
+enum CaseFilter {
+    // id が一致するケース
+    Id(String),
+    // 番号が from 以上 to 以下のサンプル
+    Range(u32, u32),
+}
+
+impl CaseFilter {
+    fn matches(&self, case: &TestCase) -> bool {
+        match self {
+            CaseFilter::Id(id) => case.id == *id,
+            CaseFilter::Range(from, to) => {
+                !case.custom
+                    && case
+                        .id
+                        .parse()
+                        .is_ok_and(|n: u32| (*from..=*to).contains(&n))
+            }
+        }
+    }
+}
+
+fn parse_case_filter(s: &str) -> anyhow::Result<CaseFilter> {
+    // "3" や "gen_1" は id、"2..5" は範囲 (両端を含む) として解釈する
+    let Some((from, to)) = s.split_once("..") else {
+        return Ok(CaseFilter::Id(s.to_string()));
+    };
+    let parse = |n: &str| {
+        n.trim()
+            .parse::<u32>()
+            .map_err(|_| anyhow::anyhow!("ケースの範囲が不正です: {}", s))
+    };
+    let (from, to) = (parse(from)?, parse(to)?);
+    if from > to {
+        return Err(anyhow::anyhow!("ケースの範囲が不正です: {}", s));
+    }
+    Ok(CaseFilter::Range(from, to))
+}
+
+// atk test の既定の実行時間制限 (ms)
+const DEFAULT_TIME_LIMIT_MS: u64 = 2000;
+
+// 問題ごとに生成したテンプレートの保存先 (コンテストディレクトリからの相対パス)
+const TEMPLATE_DIR: &str = ".atk/templates";
+
+// atk test の終了コード
+// 複数の種類の失敗がある場合は CE > RE > TLE > WA の順に優先する
+const EXIT_WRONG_ANSWER: i32 = 10;
+const EXIT_RUNTIME_ERROR: i32 = 11;
+const EXIT_TIME_LIMIT_EXCEEDED: i32 = 12;
+const EXIT_COMPILE_ERROR: i32 = 13;
+
+fn exit_code(results: &[CaseResult]) -> i32 {
+    // 全ての結果のうち最も優先度の高い失敗に対応する終了コード
+    let has = |verdicts: &[Verdict]| results.iter().any(|r| verdicts.contains(&r.verdict));
+    if has(&[Verdict::RE, Verdict::Sanitizer]) {
+        EXIT_RUNTIME_ERROR
+    } else if has(&[Verdict::TLE]) {
+        EXIT_TIME_LIMIT_EXCEEDED
+    } else if has(&[Verdict::WA]) {
+        EXIT_WRONG_ANSWER
+    } else {
+        0
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+}
+
+#[derive(Debug)]
+struct TestOptions {
+    serial: bool,
+    debug: bool,
+    full_diff: bool,
+    time_limit: std::time::Duration,
+    format: OutputFormat,
+    // 空の場合は全てのケースを実行する
+    cases: Vec<CaseFilter>,
+    only_custom: bool,
+}
+
+fn compile_message(error: &anyhow::Error) -> &str {
+    // CompileError の場合はコンパイラのエラーメッセージを返す
+    error
+        .downcast_ref::<compile::CompileError>()
+        .map_or("", |error| error.message.as_str())
+}
+
+fn make_reporter(problem_char: &str, options: &TestOptions) -> Box<dyn Reporter> {
+    match options.format {
+        OutputFormat::Human => Box::new(report::HumanReporter {
+            show_stderr: options.debug,
+            full_diff: options.full_diff,
+            time_limit: options.time_limit,
+        }),
+        OutputFormat::Json => Box::new(report::JsonReporter {
+            problem: problem_char.to_string(),
+        }),
+    }
+}
+
+fn judge(
+    id: String,
+    expected: &str,
+    execution: exec::Execution,
+    time_limit: std::time::Duration,
+    debug: bool,
+) -> CaseResult {
+    /*
+    実行結果を期待する出力と比べて判定する
+    args:
+        id: ケースの識別子
+        expected: 期待する出力
+        execution: 実行結果
+        time_limit: 実行時間制限
+        debug: DEBUG_FLAGS でビルドしたかどうか (サニタイザの報告を探す)
+    returns:
+        判定結果
+    */
+    let exec::Execution {
+        output,
+        elapsed,
+        memory,
+        timed_out,
+    } = execution;
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    let expected = normalize_output(expected);
+    let actual = normalize_output(&String::from_utf8_lossy(&output.stdout));
+
+    let (verdict, detail) = if timed_out {
+        (Verdict::TLE, None)
+    } else if debug && let Some(report) = sanitizer_report(&stderr) {
+        (Verdict::Sanitizer, Some(report))
+    } else if !output.status.success() {
+        (Verdict::RE, Some(exec::exit_description(&output.status)))
+    } else if elapsed > time_limit {
+        // 制限時間を超えたが、止める前に終了した
+        (Verdict::TLE, None)
+    } else if actual == expected {
+        (Verdict::AC, None)
+    } else {
+        (Verdict::WA, None)
+    };
+
+    CaseResult {
+        id,
+        verdict,
+        elapsed,
+        memory,
+        timed_out,
+        expected,
+        actual,
+        stderr,
+        detail,
+    }
+}
+
+async fn run_tests(
+    contest_id: String,
+    problem_char: String,
+    options: &TestOptions,
+    config: &config::Config,
+    reporter: &mut dyn Reporter,
+) -> anyhow::Result<Vec<CaseResult>> {
     /*
     指定されたコンテストIDと問題文字に基づいて、AtCoderのサンプルケースを取得し、ローカルでテストを実行する
     args:
         contest_id: コンテストID (例: "abc123")
         problem_char: 問題文字 (例: "a", "b", "c" など)
+        options: テストの実行オプション
+        config: atk の設定
+        reporter: 結果の出力先
+    returns:
+        各テストケースの判定結果
     */
 
     let problem_file = format!("{}.cpp", &problem_char);
@@ -593,116 +1377,780 @@ async fn run_tests(contest_id: String, problem_char: String) -> anyhow::Result<(
         return Err(anyhow::anyhow!("{} が存在しません", problem_path.display()));
     }
 
-    let samples = get_sample_cases_cached(contest_id, &problem_char).await?;
-    if samples.is_empty() {
+    let samples = if options.only_custom {
+        // 自分で追加したケースだけを使うので、サンプルは取得しない (オフラインでも動く)
+        let test_dir = PathBuf::from(".test").join(&problem_char);
+        if test_dir.exists() {
+            load_cached_cases(&test_dir)?
+        } else {
+            Vec::new()
+        }
+    } else {
+        get_sample_cases_cached(contest_id, &problem_char).await?
+    };
+    if samples.is_empty() && !options.only_custom {
         return Err(anyhow::anyhow!("サンプルケースが見つかりませんでした"));
     }
+    let samples: Vec<TestCase> = samples
+        .into_iter()
+        .filter(|case| !options.only_custom || case.custom)
+        .filter(|case| options.cases.is_empty() || options.cases.iter().any(|f| f.matches(case)))
+        .collect();
+    if samples.is_empty() {
+        return Err(anyhow::anyhow!("条件に一致するテストケースがありません"));
+    }
 
-    let executable_name = format!("{}.out", problem_char);
-    let executable_path = current_dir()?.join(executable_name);
+    let executable_path = compile::compile_cpp(&problem_path, config, options.debug)?;
 
-    let compile_output = std::process::Command::new("g++")
-        .arg(problem_path)
-        .arg("-o")
-        .arg(&executable_path)
-        .output()
-        .expect("コンパイルに失敗しました");
+    let jobs = if options.serial {
+        1
+    } else {
+        std::thread::available_parallelism().map_or(1, |n| n.get())
+    };
 
-    if !compile_output.status.success() {
-        eprintln!(
-            "コンパイルエラー:\n{}",
-            String::from_utf8_lossy(&compile_output.stderr)
+    let mut results = Vec::new();
+    run_cases(
+        &executable_path,
+        &samples,
+        jobs,
+        options.time_limit,
+        |i, execution| {
+            let result = judge(
+                samples[i].id.clone(),
+                &samples[i].output,
+                execution,
+                options.time_limit,
+                options.debug,
+            );
+            reporter.case_finished(&result);
+            results.push(result);
+        },
+    )?;
+    reporter.finish(&results);
+
+    Ok(results)
+}
+
+fn run_cases(
+    executable: &Path,
+    cases: &[TestCase],
+    jobs: usize,
+    time_limit: std::time::Duration,
+    mut on_result: impl FnMut(usize, exec::Execution),
+) -> anyhow::Result<()> {
+    /*
+    テストケースを最大 jobs 個並列に実行し、結果をケースの順番通りに on_result へ渡す
+    args:
+        executable: 実行ファイルのパス
+        cases: テストケースのリスト
+        jobs: 同時に実行するプロセス数
+        time_limit: 各ケースの実行時間制限
+        on_result: (ケース番号, 実行結果) を受け取るコールバック
+    */
+    exec::run_parallel(
+        cases.len(),
+        jobs,
+        |i| exec::run_executable(executable, &cases[i].input, Some(time_limit)),
+        |i, execution| {
+            on_result(i, execution?);
+            Ok(())
+        },
+    )
+}
+
+fn is_problem_file(stem: &str) -> bool {
+    // a.cpp, ex.cpp, 001.cpp のような問題のファイル名かどうか
+    let letters = (1..=2).contains(&stem.len()) && stem.chars().all(|c| c.is_ascii_lowercase());
+    let digits = !stem.is_empty() && stem.chars().all(|c| c.is_ascii_digit());
+    letters || digits
+}
+
+fn is_untouched(path: &Path) -> anyhow::Result<bool> {
+    /*
+    ファイルが作成時のテンプレートから変更されていないかどうか
+    */
+    let source = fs::read_to_string(path)?;
+    if source == CPP_TEMPLATE {
+        return Ok(true);
+    }
+    let template_path = path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(TEMPLATE_DIR)
+        .join(path.file_name().unwrap_or_default());
+    Ok(fs::read_to_string(template_path).is_ok_and(|template| template == source))
+}
+
+enum ProblemStatus {
+    Untouched,
+    Tested(Vec<CaseResult>),
+    CompileError,
+    Error(String),
+}
+
+async fn test_all(
+    contest_id: String,
+    options: &TestOptions,
+    config: &config::Config,
+) -> anyhow::Result<i32> {
+    /*
+    コンテストディレクトリ内の全ての問題のサンプルを実行し、結果を表にまとめる
+    テンプレートから変更されていないファイルは飛ばす
+    args:
+        contest_id: コンテストID (例: "abc123")
+        options: テストの実行オプション
+        config: atk の設定
+    returns:
+        終了コード (全ての問題の結果のうち最も優先度の高い失敗に対応する。テストできなかった問題があれば少なくとも 1)
+    */
+    let mut problems: Vec<(String, PathBuf)> = fs::read_dir(current_dir()?)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "cpp"))
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_string_lossy().to_string();
+            is_problem_file(&stem).then_some((stem, path))
+        })
+        .collect();
+    problems.sort_by(|a, b| (a.0.len(), &a.0).cmp(&(b.0.len(), &b.0)));
+    if problems.is_empty() {
+        return Err(anyhow::anyhow!("問題のファイルが見つかりませんでした"));
+    }
+
+    let mut statuses = Vec::new();
+    for (problem, path) in problems {
+        if is_untouched(&path)? {
+            statuses.push((problem, ProblemStatus::Untouched));
+            continue;
+        }
+        eprintln!("{} をテストしています...", path.display());
+        let status = match run_tests(
+            contest_id.clone(),
+            problem.clone(),
+            options,
+            config,
+            &mut report::SilentReporter,
+        )
+        .await
+        {
+            Err(e) if e.is::<compile::CompileError>() => ProblemStatus::CompileError,
+            Err(e) => ProblemStatus::Error(e.to_string()),
+            results => ProblemStatus::Tested(results?),
+        };
+        statuses.push((problem, status));
+    }
+
+    println!();
+    println!(
+        "{:<8} {:<8} {:>7} {:>10}",
+        "problem", "result", "cases", "max time"
+    );
+    let mut code = 0;
+    for (problem, status) in &statuses {
+        let (result, cases, time) = match status {
+            ProblemStatus::Untouched => ("-".dimmed(), String::new(), String::new()),
+            ProblemStatus::CompileError => {
+                code = code.max(EXIT_COMPILE_ERROR);
+                ("CE".yellow().bold(), String::new(), String::new())
+            }
+            ProblemStatus::Error(message) => {
+                eprintln!("{}: {}", problem, message);
+                // 他に失敗がなくても成功扱いにはしない
+                code = code.max(1);
+                ("ERROR".red().bold(), String::new(), String::new())
+            }
+            ProblemStatus::Tested(results) => {
+                code = code.max(exit_code(results));
+                let passed = results.iter().filter(|r| r.verdict == Verdict::AC).count();
+                // AC 以外で最も優先度の高い判定を代表として表示する
+                let verdict = [Verdict::RE, Verdict::Sanitizer, Verdict::TLE, Verdict::WA]
+                    .into_iter()
+                    .find(|v| results.iter().any(|r| r.verdict == *v))
+                    .unwrap_or(Verdict::AC);
+                let max = results.iter().map(|r| r.elapsed).max().unwrap_or_default();
+                (
+                    verdict.label(),
+                    format!("{}/{}", passed, results.len()),
+                    format!("{}ms", max.as_millis()),
+                )
+            }
+        };
+        // 色付きの文字列は幅の指定が効かないため、空白を自分で足す
+        let padding = " ".repeat(8usize.saturating_sub(result.chars().count()));
+        println!(
+            "{:<8} {}{} {:>7} {:>10}",
+            problem, result, padding, cases, time
         );
-        return Err(anyhow::anyhow!("コンパイルに失敗しました"));
     }
+    Ok(code)
+}
 
-    for (i, sample) in samples.iter().enumerate() {
-        println!(" --- Running test case {} ---", i + 1);
+async fn watch_tests(
+    contest_id: String,
+    problem_char: String,
+    options: &TestOptions,
+    config: &config::Config,
+) -> anyhow::Result<()> {
+    /*
+    ソースファイルの変更を監視し、保存されるたびにコンパイルとテストをやり直す
+    args:
+        contest_id: コンテストID (例: "abc123")
+        problem_char: 問題文字 (例: "a", "b", "c" など)
+        options: テストの実行オプション
+        config: atk の設定
+    */
+    use notify::{EventKind, RecursiveMode, Watcher};
+
+    let problem_file = format!("{}.cpp", &problem_char);
+    let problem_path = current_dir()?.join(&problem_file);
+    if !problem_path.exists() {
+        return Err(anyhow::anyhow!("{} が存在しません", problem_path.display()));
+    }
+
+    // エディタによっては別名で書き込んでから置き換えるため、ディレクトリごと監視する
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&current_dir()?, RecursiveMode::NonRecursive)?;
+
+    // JSON の場合は標準出力を JSON だけにするため、人間向けの表示は標準エラー出力に出す
+    let json = matches!(options.format, OutputFormat::Json);
+    let show = |text: String| {
+        if json {
+            eprint!("{}", text);
+        } else {
+            print!("{}", text);
+        }
+    };
+
+    loop {
+        // 画面をクリアしてから実行する
+        show("\x1b[2J\x1b[H".to_string());
+        let mut reporter = make_reporter(&problem_char, options);
+        let result = run_tests(
+            contest_id.clone(),
+            problem_char.clone(),
+            options,
+            config,
+            reporter.as_mut(),
+        )
+        .await;
+        match result {
+            std::result::Result::Ok(results) => {
+                let labels = results
+                    .iter()
+                    .map(|r| r.verdict.label().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                show(format!("[{}]\n", labels));
+                for result in &results {
+                    if let Some(detail) = &result.detail {
+                        show(format!(
+                            "  case {}: {} {}\n",
+                            result.id,
+                            result.verdict.label(),
+                            detail
+                        ));
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("{} {}", "error:".red().bold(), e);
+                if e.is::<compile::CompileError>() {
+                    reporter.compile_error(compile_message(&e));
+                }
+            }
+        }
+        show(format!(
+            "{}\n",
+            format!("{} の変更を監視しています (Ctrl-C で終了)", problem_file).dimmed()
+        ));
+
+        // 対象ファイルへの書き込みを待つ
+        loop {
+            if let Some(event) = rx.recv()?.ok()
+                && matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                && event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name() == problem_path.file_name())
+            {
+                break;
+            }
+        }
+        // 保存時に連続して届くイベントをまとめる
+        while rx
+            .recv_timeout(std::time::Duration::from_millis(200))
+            .is_ok()
+        {}
+    }
+}
 
-        let mut test_output = std::process::Command::new(&executable_path)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()?;
+fn run_program(
+    problem_char: &str,
+    input: Option<&Path>,
+    debug: bool,
+    time_limit: Option<std::time::Duration>,
+    config: &config::Config,
+) -> anyhow::Result<i32> {
+    /*
+    出力を比較せずにプログラムを実行し、実行時間と最大使用メモリを表示する
+    args:
+        problem_char: 問題文字 (例: "a", "b", "c" など)
+        input: 標準入力として与えるファイル (None の場合は端末から入力する)
+        debug: atk test --debug と同じフラグでビルドするかどうか
+        time_limit: 実行時間制限 (None の場合は制限しない)
+        config: atk の設定
+    returns:
+        atk test と同じ終了コード (正常終了は 0、RE は 11、TLE は 12)
+    */
+    let problem_path = current_dir()?.join(format!("{}.cpp", problem_char));
+    if !problem_path.exists() {
+        return Err(anyhow::anyhow!("{} が存在しません", problem_path.display()));
+    }
+    let stdin = match input {
+        Some(path) => Stdio::from(
+            fs::File::open(path)
+                .map_err(|e| anyhow::anyhow!("{} を開けません: {}", path.display(), e))?,
+        ),
+        None => {
+            use std::io::IsTerminal;
+            if std::io::stdin().is_terminal() {
+                eprintln!("{}", "入力を待っています (Ctrl-D で終了)".dimmed());
+            }
+            Stdio::inherit()
+        }
+    };
+    let executable_path = compile::compile_cpp(&problem_path, config, debug)?;
+
+    let start = std::time::Instant::now();
+    let mut child = exec::program_command(&executable_path)
+        .stdin(stdin)
+        .spawn()?;
+    let (status, memory, timed_out) = exec::wait_with_limit(&mut child, start, time_limit)?;
+    let elapsed = start.elapsed();
+
+    let (description, code) = if timed_out {
+        ("TLE".to_string(), EXIT_TIME_LIMIT_EXCEEDED)
+    } else if status.success() {
+        ("exit code 0".to_string(), 0)
+    } else {
+        (exec::exit_description(&status), EXIT_RUNTIME_ERROR)
+    };
+    // 端末から入力した場合は入力を待つ時間も含まれる
+    let summary = format!(
+        "--- {}, {}ms, {} ---",
+        description,
+        elapsed.as_millis(),
+        report::format_memory(memory)
+    );
+    if code == 0 {
+        eprintln!("{}", summary.dimmed());
+    } else {
+        eprintln!("{}", summary.red().bold());
+    }
+    Ok(code)
+}
 
+fn sanitizer_report(stderr: &str) -> Option<String> {
+    /*
+    標準エラー出力からサニタイザ (ASan / UBSan) や _GLIBCXX_DEBUG の報告を探す
+    returns:
+        報告の要約 (例: "AddressSanitizer: heap-buffer-overflow")
+    */
+    for line in stderr.lines() {
+        if let Some((_, rest)) = line.split_once("ERROR: ")
+            && rest.contains("Sanitizer")
         {
-            let child_stdin = test_output.stdin.as_mut().unwrap();
-            child_stdin.write_all(sample.input.as_bytes())?;
-        } // 標準入力を閉じる
+            // ERROR: AddressSanitizer: heap-buffer-overflow on address 0x... から種類だけ取り出す
+            return Some(rest.split(" on ").next().unwrap_or(rest).trim().to_string());
+        }
+        if line.contains(": runtime error: ") {
+            return Some(format!("UndefinedBehaviorSanitizer: {}", line.trim()));
+        }
+        if let Some((_, rest)) = line.split_once("Error: attempt to") {
+            return Some(format!("_GLIBCXX_DEBUG: attempt to{}", rest));
+        }
+    }
+    None
+}
 
-        let output = test_output.wait_with_output()?;
+fn normalize_output(output: &str) -> String {
+    // 行末と末尾の空白を無視して比較するために整形する
+    output
+        .trim_end()
+        .split('\n')
+        .map(|s| s.trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-        if !output.status.success() {
-            eprintln!("{}", "    Runtime error ".red().bold());
-            eprintln!("{}", String::from_utf8_lossy(&output.stderr));
-            continue;
+fn load_generator_spec(
+    problem_char: &str,
+    spec: Option<PathBuf>,
+) -> anyhow::Result<generator::Spec> {
+    let spec_path = spec.unwrap_or_else(|| PathBuf::from(format!("{}.gen", problem_char)));
+    if !spec_path.exists() {
+        return Err(anyhow::anyhow!(
+            "仕様ファイル {} が存在しません",
+            spec_path.display()
+        ));
+    }
+    generator::parse_spec(&fs::read_to_string(&spec_path)?)
+}
+
+fn default_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+fn next_generated_index(test_dir: &Path) -> usize {
+    // 既存の gen_in<n>.txt と重ならない番号を返す
+    let mut idx = 1;
+    while test_dir.join(format!("gen_in{}.txt", idx)).exists() {
+        idx += 1;
+    }
+    idx
+}
+
+fn generate_inputs(
+    problem_char: &str,
+    spec: Option<PathBuf>,
+    seed: Option<u64>,
+    count: u64,
+    save: bool,
+    naive: Option<PathBuf>,
+    config: &config::Config,
+) -> anyhow::Result<()> {
+    /*
+    仕様ファイルからランダムな入力を生成する
+    args:
+        problem_char: 問題文字 (例: "a", "b", "c" など)
+        spec: 仕様ファイルのパス
+        seed: 乱数シード (i 個目の入力は seed + i で生成する)
+        count: 生成する個数
+        save: .test/<problem>/ に期待出力と合わせて保存するかどうか
+        naive: 期待出力を作る解答 (省略時は <problem>.cpp)
+        config: atk の設定
+    */
+    let spec = load_generator_spec(problem_char, spec)?;
+    let seed = seed.unwrap_or_else(default_seed);
+    let test_dir = PathBuf::from(".test").join(problem_char);
+    let reference = if save {
+        let source = naive.unwrap_or_else(|| PathBuf::from(format!("{}.cpp", problem_char)));
+        if !source.exists() {
+            return Err(anyhow::anyhow!("{} が存在しません", source.display()));
         }
+        fs::create_dir_all(&test_dir)?;
+        Some(compile::compile_cpp(&source, config, false)?)
+    } else {
+        None
+    };
 
-        let acutual_output = String::from_utf8_lossy(&output.stdout).to_string();
+    for i in 0..count {
+        let case_seed = seed.wrapping_add(i);
+        let input = spec.generate(&mut StdRng::seed_from_u64(case_seed))?;
+        if let Some(reference) = &reference {
+            let expected = exec::run_executable(reference, &input, None)?.output;
+            if !expected.status.success() {
+                report::print_runtime_error(
+                    &exec::exit_description(&expected.status),
+                    &String::from_utf8_lossy(&expected.stderr),
+                );
+                return Err(anyhow::anyhow!(
+                    "seed {} の期待出力を作る解答が Runtime error になりました",
+                    case_seed
+                ));
+            }
+            let idx = next_generated_index(&test_dir);
+            let in_path = test_dir.join(format!("gen_in{}.txt", idx));
+            fs::write(&in_path, &input)?;
+            fs::write(
+                test_dir.join(format!("gen_out{}.txt", idx)),
+                normalize_output(&String::from_utf8_lossy(&expected.stdout)) + "\n",
+            )?;
+            println!("seed {} -> {}", case_seed, in_path.display());
+        } else {
+            eprintln!("{}", format!("# seed {}", case_seed).dimmed());
+            print!("{}", input);
+        }
+    }
+    Ok(())
+}
 
-        let normalized_actual = acutual_output
-            .trim_end()
-            .split('\n')
-            .map(|s| s.trim_end())
-            .collect::<Vec<_>>()
-            .join("\n");
-        let normalized_expected = sample
-            .output
-            .trim_end()
-            .split('\n')
-            .map(|s| s.trim_end())
-            .collect::<Vec<_>>()
-            .join("\n");
+fn stress_test(
+    problem_char: &str,
+    naive: &Path,
+    spec: Option<PathBuf>,
+    seed: Option<u64>,
+    count: u64,
+    config: &config::Config,
+) -> anyhow::Result<()> {
+    /*
+    ランダムな入力で解答と愚直解の出力を比較し、食い違う入力を探す
+    見つかった場合は .test/<problem>/gen_in<n>.txt, gen_out<n>.txt に保存する
+    args:
+        problem_char: 問題文字 (例: "a", "b", "c" など)
+        naive: 愚直解のソースファイル
+        spec: 仕様ファイルのパス
+        seed: 乱数シード
+        count: 試行回数
+        config: atk の設定
+    */
+    let problem_path = current_dir()?.join(format!("{}.cpp", problem_char));
+    if !problem_path.exists() {
+        return Err(anyhow::anyhow!("{} が存在しません", problem_path.display()));
+    }
+    if !naive.exists() {
+        return Err(anyhow::anyhow!("{} が存在しません", naive.display()));
+    }
+    let spec = load_generator_spec(problem_char, spec)?;
+    let seed = seed.unwrap_or_else(default_seed);
+
+    let executable_path = compile::compile_cpp(&problem_path, config, false)?;
+    let naive_path = compile::compile_cpp(naive, config, false)?;
+
+    let mut found = None;
+    for i in 0..count {
+        let case_seed = seed.wrapping_add(i);
+        let input = spec.generate(&mut StdRng::seed_from_u64(case_seed))?;
+
+        let expected = exec::run_executable(&naive_path, &input, None)?.output;
+        if !expected.status.success() {
+            eprintln!("{}", "    愚直解が Runtime error になりました".red().bold());
+            report::print_runtime_error(
+                &exec::exit_description(&expected.status),
+                &String::from_utf8_lossy(&expected.stderr),
+            );
+            found = Some((case_seed, input, None));
+            break;
+        }
+        let expected = normalize_output(&String::from_utf8_lossy(&expected.stdout));
 
-        if normalized_actual == normalized_expected {
+        let actual = exec::run_executable(&executable_path, &input, None)?.output;
+        if !actual.status.success() {
             println!(
-                " --- Test Case {}: {} ---",
-                (i + 1).to_string().green(),
-                "AC".green().bold()
+                " --- seed {}: {} ---",
+                case_seed.to_string().yellow(),
+                Verdict::RE.label()
             );
-        } else {
+            report::print_runtime_error(
+                &exec::exit_description(&actual.status),
+                &String::from_utf8_lossy(&actual.stderr),
+            );
+            found = Some((case_seed, input, Some(expected)));
+            break;
+        }
+        let actual = normalize_output(&String::from_utf8_lossy(&actual.stdout));
+
+        if actual != expected {
             println!(
-                " --- Test Case {}: {} ---",
-                (i + 1).to_string().yellow(),
+                " --- seed {}: {} ---",
+                case_seed.to_string().yellow(),
                 "WA".red().bold()
             );
-            println!("Expected:\n{}", normalized_expected);
-            println!("Actual:\n{}", normalized_actual);
+            println!("Input:\n{}", input.trim_end());
+            diff::print_diff(&expected, &actual, false);
+            found = Some((case_seed, input, Some(expected)));
+            break;
         }
     }
 
-    fs::remove_file(&executable_path)?;
-    return Ok(());
+    match found {
+        Some((case_seed, input, expected)) => {
+            let test_dir = PathBuf::from(".test").join(problem_char);
+            fs::create_dir_all(&test_dir)?;
+            let idx = next_generated_index(&test_dir);
+            fs::write(test_dir.join(format!("gen_in{}.txt", idx)), &input)?;
+            if let Some(expected) = expected {
+                fs::write(
+                    test_dir.join(format!("gen_out{}.txt", idx)),
+                    expected + "\n",
+                )?;
+            }
+            println!(
+                "seed {} の入力を {} に保存しました",
+                case_seed,
+                test_dir.join(format!("gen_in{}.txt", idx)).display()
+            );
+            Err(anyhow::anyhow!(
+                "解答と愚直解の出力が一致しない入力が見つかりました"
+            ))
+        }
+        None => {
+            println!(
+                "{} {} 個のランダムケースで出力が一致しました (seed {} ~ {})",
+                style("finished").green(),
+                count,
+                seed,
+                seed.wrapping_add(count.saturating_sub(1))
+            );
+            Ok(())
+        }
+    }
+}
+
+fn bundle_source(
+    file_path: &Path,
+    expand_acl: bool,
+    config: &config::Config,
+) -> anyhow::Result<String> {
+    let library_paths = config.library_paths()?;
+    let acl_dir = config.acl_dir()?;
+    bundle::bundle(
+        file_path,
+        &bundle::BundleOptions {
+            library_paths: &library_paths,
+            acl_dir: expand_acl.then_some(acl_dir.as_path()),
+        },
+    )
+}
+
+async fn check_before_copy(
+    contest_id: &str,
+    problem_char: &str,
+    file_path: &Path,
+    bundled: &str,
+    config: &config::Config,
+) -> anyhow::Result<Vec<String>> {
+    /*
+    提出前にコードとサンプルの結果を確認する
+    args:
+        contest_id: コンテストID (例: "abc123")
+        problem_char: 問題文字 (例: "a", "b", "c" など)
+        file_path: 問題のファイル
+        bundled: ライブラリを展開した提出用のコード
+        config: atk の設定
+    returns:
+        見つかった問題の一覧
+    */
+    let mut warnings = Vec::new();
+    let file_name = file_path.display();
+    if is_untouched(file_path)? {
+        warnings.push(format!("{} がテンプレートのままです", file_name));
+    }
+    // 提出時に取り除かれるデバッグ用のコードは調べない
+    let source = strip::strip_keeping_lines(&fs::read_to_string(file_path)?, &config.strip)?;
+    for warning in lint::lint_source(&source) {
+        warnings.push(format!(
+            "{}:{}: {}",
+            file_name, warning.line, warning.message
+        ));
+    }
+    for warning in lint::unexpanded_includes(bundled) {
+        warnings.push(format!(
+            "展開後の {} 行目: {}",
+            warning.line, warning.message
+        ));
+    }
+
+    let options = TestOptions {
+        serial: false,
+        debug: false,
+        full_diff: false,
+        time_limit: std::time::Duration::from_millis(DEFAULT_TIME_LIMIT_MS),
+        format: OutputFormat::Human,
+        cases: Vec::new(),
+        only_custom: false,
+    };
+    eprintln!("サンプルを実行しています...");
+    match run_tests(
+        contest_id.to_string(),
+        problem_char.to_string(),
+        &options,
+        config,
+        &mut report::SilentReporter,
+    )
+    .await
+    {
+        Err(e) if e.is::<compile::CompileError>() => {
+            warnings.push("コンパイルに失敗しました".to_string());
+        }
+        Err(e) => warnings.push(format!("サンプルを実行できませんでした: {}", e)),
+        results => {
+            let failed: Vec<String> = results?
+                .iter()
+                .filter(|r| r.verdict != Verdict::AC)
+                .map(|r| format!("{} ({})", r.id, r.verdict.name()))
+                .collect();
+            if !failed.is_empty() {
+                warnings.push(format!("サンプルが通っていません: {}", failed.join(", ")));
+            }
+        }
+    }
+    Ok(warnings)
 }
 
-fn copy_problem_template(_contest_id: &str, problem_char: String) -> anyhow::Result<()> {
+async fn copy_problem_template(
+    contest_id: &str,
+    problem_char: String,
+    expand_acl: bool,
+    force: bool,
+    config: &config::Config,
+) -> anyhow::Result<()> {
     // 問題ファイル名
     let file_name = format!("{}.cpp", problem_char);
     let file_path = PathBuf::from(&file_name);
     if !file_path.exists() {
         return Err(anyhow::anyhow!("{} が存在しません", file_path.display()));
     }
-    let code = fs::read_to_string(&file_path)?;
-    // xclipでクリップボードにコピー
-    use std::process::Command;
-    let mut child = Command::new("xclip")
-        .arg("-selection")
-        .arg("clipboard")
-        .stdin(Stdio::piped())
-        .spawn()
-        .map_err(|e| anyhow::anyhow!("xclipの起動に失敗: {}", e))?;
-    {
-        let stdin = child
-            .stdin
-            .as_mut()
-            .ok_or_else(|| anyhow::anyhow!("xclipのstdin取得に失敗"))?;
-        stdin.write_all(code.as_bytes())?;
-    }
-    let status = child.wait()?;
-    if !status.success() {
-        return Err(anyhow::anyhow!(
-            "xclipによるクリップボードコピーに失敗しました"
-        ));
+    // ライブラリの #include "..." を展開し、デバッグ用のコードを取り除いてからコピーする
+    let code = bundle_source(&file_path, expand_acl, config)?;
+    let code = strip::strip(&code, &config.strip)?;
+
+    if !force {
+        let warnings =
+            check_before_copy(contest_id, &problem_char, &file_path, &code, config).await?;
+        if !warnings.is_empty() {
+            eprintln!(
+                "{}",
+                "提出前のチェックで問題が見つかりました".yellow().bold()
+            );
+            for warning in &warnings {
+                eprintln!("  {}", warning);
+            }
+            use std::io::IsTerminal;
+            if !std::io::stdin().is_terminal() {
+                return Err(anyhow::anyhow!(
+                    "コピーを中止しました (--force でチェックを省略できます)"
+                ));
+            }
+            let proceed = Confirm::new()
+                .with_prompt("それでもコピーしますか?")
+                .default(false)
+                .interact()?;
+            if !proceed {
+                println!("処理を中止しました");
+                return Ok(());
+            }
+        }
     }
-    println!("{} をクリップボードにコピーしました", file_name);
+    let backend = clip::copy(&code, config.clipboard.backend)?;
+    println!(
+        "{} をクリップボードにコピーしました ({})",
+        file_name, backend
+    );
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_case_names() {
+        let cases = [
+            ("in1", Some(("", "1"))),
+            ("in_extra", Some(("", "_extra"))),
+            ("gen_in1", Some(("gen_", "1"))),
+            ("join_in1", Some(("join_", "1"))),
+            ("min_in2", Some(("min_", "2"))),
+            ("my_case_in3", Some(("my_case_", "3"))),
+            ("main", None),
+            ("out1", None),
+        ];
+        for (stem, expected) in cases {
+            assert_eq!(split_case_name(stem), expected, "{}", stem);
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use crate::diff;
+use colored::Colorize;
+use serde::Serialize;
+use std::time::Duration;
+
+// ----------------------------------------------  //
+// テスト結果の出力
+// run_tests は各ケースの判定結果 (CaseResult) を Reporter に渡し、
+// Reporter が人間向けの表示や JSON などの形式で出力する
+// ----------------------------------------------  //
+
+// 競プロの判定名をそのまま使う
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Verdict {
+    AC,
+    WA,
+    RE,
+    TLE,
+    // サニタイザや _GLIBCXX_DEBUG がエラーを検出した
+    #[serde(rename = "SAN")]
+    Sanitizer,
+}
+
+impl Verdict {
+    pub fn label(&self) -> colored::ColoredString {
+        match self {
+            Verdict::AC => "AC".green().bold(),
+            Verdict::WA => "WA".red().bold(),
+            Verdict::RE => "RE".red().bold(),
+            Verdict::TLE => "TLE".yellow().bold(),
+            Verdict::Sanitizer => "SAN".magenta().bold(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Verdict::AC => "AC",
+            Verdict::WA => "WA",
+            Verdict::RE => "RE",
+            Verdict::TLE => "TLE",
+            Verdict::Sanitizer => "SAN",
+        }
+    }
+}
+
+pub struct CaseResult {
+    pub id: String,
+    pub verdict: Verdict,
+    pub elapsed: Duration,
+    // 最大使用メモリ (KB, 実行中に読んだ値のため概算)
+    pub memory: Option<u64>,
+    // 実行時間制限を超えたため止めた
+    pub timed_out: bool,
+    pub expected: String,
+    pub actual: String,
+    pub stderr: String,
+    // RE の場合の終了理由 (例: "SIGSEGV (signal 11)") やサニタイザの報告
+    pub detail: Option<String>,
+}
+
+pub trait Reporter {
+    // ケースの判定が終わるたびに、ケースの順番通りに呼ばれる
+    fn case_finished(&mut self, result: &CaseResult);
+    // 全てのケースの判定が終わったときに呼ばれる
+    fn finish(&mut self, results: &[CaseResult]);
+    // コンパイルに失敗したときに呼ばれる (エラーの内容は標準エラー出力に表示済み)
+    fn compile_error(&mut self, _message: &str) {}
+}
+
+pub fn summary_line(results: &[CaseResult]) -> String {
+    /*
+    テスト結果の要約 (例: "3/4 AC, 1 WA, max time 120ms")
+    */
+    let passed = results.iter().filter(|r| r.verdict == Verdict::AC).count();
+    let mut parts = vec![format!("{}/{} AC", passed, results.len())];
+    for verdict in [Verdict::WA, Verdict::RE, Verdict::TLE, Verdict::Sanitizer] {
+        let count = results.iter().filter(|r| r.verdict == verdict).count();
+        if count > 0 {
+            parts.push(format!("{} {}", count, verdict.name()));
+        }
+    }
+    if let Some(max) = results.iter().map(|r| r.elapsed).max() {
+        parts.push(format!("max time {}ms", max.as_millis()));
+    }
+    let line = parts.join(", ");
+    if passed == results.len() {
+        line.green().bold().to_string()
+    } else {
+        line.red().bold().to_string()
+    }
+}
+
+// RE の際に表示する標準エラー出力の最大行数
+const STDERR_TAIL_LINES: usize = 20;
+
+fn stderr_tail(stderr: &str) -> String {
+    // 長い標準エラー出力は末尾の STDERR_TAIL_LINES 行だけにする
+    let lines: Vec<&str> = stderr.trim_end().lines().collect();
+    if lines.len() <= STDERR_TAIL_LINES {
+        return lines.join("\n");
+    }
+    let omitted = lines.len() - STDERR_TAIL_LINES;
+    format!("... ({} 行省略)\n{}", omitted, lines[omitted..].join("\n"))
+}
+
+pub fn print_runtime_error(detail: &str, stderr: &str) {
+    eprintln!("{} {}", "    Runtime error:".red().bold(), detail);
+    let tail = stderr_tail(stderr);
+    if !tail.is_empty() {
+        eprintln!("{}", tail);
+    }
+}
+
+pub fn format_memory(memory: Option<u64>) -> String {
+    match memory {
+        Some(kb) => format!("{}KB", kb),
+        None => "-".to_string(),
+    }
+}
+
+pub struct HumanReporter {
+    // 全てのケースの標準エラー出力を表示する
+    pub show_stderr: bool,
+    // WA のとき、出力を省略せずに表示する
+    pub full_diff: bool,
+    pub time_limit: Duration,
+}
+
+impl Reporter for HumanReporter {
+    fn case_finished(&mut self, result: &CaseResult) {
+        println!(" --- Running test case {} ---", result.id);
+        let time = if result.timed_out {
+            format!(">{}ms", self.time_limit.as_millis())
+        } else {
+            format!("{}ms", result.elapsed.as_millis())
+        };
+        let id = if result.verdict == Verdict::AC {
+            result.id.green()
+        } else {
+            result.id.yellow()
+        };
+        println!(
+            " --- Test Case {}: {} ({}, {}) ---",
+            id,
+            result.verdict.label(),
+            time,
+            format_memory(result.memory)
+        );
+
+        match result.verdict {
+            Verdict::Sanitizer => {
+                if let Some(report) = &result.detail {
+                    eprintln!("{}", report.magenta());
+                }
+                eprintln!("{}", result.stderr);
+            }
+            Verdict::RE => {
+                print_runtime_error(result.detail.as_deref().unwrap_or_default(), &result.stderr);
+            }
+            _ => {
+                if self.show_stderr && !result.stderr.is_empty() {
+                    eprintln!("{}", "stderr:".dimmed());
+                    eprint!("{}", result.stderr);
+                }
+                if result.verdict == Verdict::WA {
+                    diff::print_diff(&result.expected, &result.actual, self.full_diff);
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self, results: &[CaseResult]) {
+        println!("{}", summary_line(results));
+    }
+}
+
+// 何も出力しない (atk test --all で結果を表にまとめる場合に使う)
+pub struct SilentReporter;
+
+impl Reporter for SilentReporter {
+    fn case_finished(&mut self, _result: &CaseResult) {}
+
+    fn finish(&mut self, _results: &[CaseResult]) {}
+}
+
+#[derive(Serialize)]
+struct JsonCase<'a> {
+    id: &'a str,
+    verdict: Verdict,
+    time_ms: u128,
+    memory_kb: Option<u64>,
+    timed_out: bool,
+    expected: &'a str,
+    actual: &'a str,
+    stderr: &'a str,
+    detail: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    problem: &'a str,
+    compile_error: bool,
+    // コンパイラのエラーメッセージ
+    compile_message: Option<&'a str>,
+    passed: usize,
+    total: usize,
+    max_time_ms: Option<u128>,
+    cases: Vec<JsonCase<'a>>,
+}
+
+pub struct JsonReporter {
+    pub problem: String,
+}
+
+impl JsonReporter {
+    fn render(&self, results: &[CaseResult], compile_message: Option<&str>) -> String {
+        let report = JsonReport {
+            problem: &self.problem,
+            compile_error: compile_message.is_some(),
+            compile_message,
+            passed: results.iter().filter(|r| r.verdict == Verdict::AC).count(),
+            total: results.len(),
+            max_time_ms: results.iter().map(|r| r.elapsed.as_millis()).max(),
+            cases: results
+                .iter()
+                .map(|r| JsonCase {
+                    id: &r.id,
+                    verdict: r.verdict,
+                    time_ms: r.elapsed.as_millis(),
+                    memory_kb: r.memory,
+                    timed_out: r.timed_out,
+                    expected: &r.expected,
+                    actual: &r.actual,
+                    stderr: &r.stderr,
+                    detail: r.detail.as_deref(),
+                })
+                .collect(),
+        };
+        // 構造体のシリアライズは失敗しない
+        serde_json::to_string(&report).unwrap()
+    }
+
+    fn print(&self, results: &[CaseResult], compile_message: Option<&str>) {
+        println!("{}", self.render(results, compile_message));
+    }
+}
+
+impl Reporter for JsonReporter {
+    // 全てのケースが終わってから1つの JSON として出力する
+    fn case_finished(&mut self, _result: &CaseResult) {}
+
+    fn finish(&mut self, results: &[CaseResult]) {
+        self.print(results, None);
+    }
+
+    fn compile_error(&mut self, message: &str) {
+        self.print(&[], Some(message));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn result(id: &str, verdict: Verdict, ms: u64) -> CaseResult {
+        CaseResult {
+            id: id.to_string(),
+            verdict,
+            elapsed: Duration::from_millis(ms),
+            memory: Some(1024),
+            timed_out: verdict == Verdict::TLE,
+            expected: "1\n".to_string(),
+            actual: "2\n".to_string(),
+            stderr: String::new(),
+            detail: None,
+        }
+    }
+
+    fn render(results: &[CaseResult], compile_message: Option<&str>) -> Value {
+        let reporter = JsonReporter {
+            problem: "a".to_string(),
+        };
+        serde_json::from_str(&reporter.render(results, compile_message)).unwrap()
+    }
+
+    #[test]
+    fn serializes_case_results() {
+        let results = [
+            result("1", Verdict::AC, 10),
+            result("2", Verdict::Sanitizer, 30),
+            result("gen_1", Verdict::TLE, 2000),
+        ];
+        let report = render(&results, None);
+        assert_eq!(report["problem"], "a");
+        assert_eq!(report["compile_error"], false);
+        assert_eq!(report["compile_message"], Value::Null);
+        assert_eq!(report["passed"], 1);
+        assert_eq!(report["total"], 3);
+        assert_eq!(report["max_time_ms"], 2000);
+        let verdicts: Vec<&Value> = report["cases"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|case| &case["verdict"])
+            .collect();
+        assert_eq!(verdicts, [&json!("AC"), &json!("SAN"), &json!("TLE")]);
+        assert_eq!(
+            report["cases"][1],
+            json!({
+                "id": "2",
+                "verdict": "SAN",
+                "time_ms": 30,
+                "memory_kb": 1024,
+                "timed_out": false,
+                "expected": "1\n",
+                "actual": "2\n",
+                "stderr": "",
+                "detail": null,
+            })
+        );
+    }
+
+    #[test]
+    fn serializes_compile_errors() {
+        let report = render(&[], Some("a.cpp:1:1: error"));
+        assert_eq!(report["compile_error"], true);
+        assert_eq!(report["compile_message"], "a.cpp:1:1: error");
+        assert_eq!(report["total"], 0);
+        assert_eq!(report["max_time_ms"], Value::Null);
+        assert_eq!(report["cases"], json!([]));
+    }
+
+    #[test]
+    fn summarizes_results() {
+        let cases = [
+            (vec![], "0/0 AC"),
+            (vec![result("1", Verdict::AC, 5)], "1/1 AC, max time 5ms"),
+            (
+                vec![
+                    result("1", Verdict::AC, 5),
+                    result("2", Verdict::WA, 7),
+                    result("3", Verdict::WA, 3),
+                    result("4", Verdict::Sanitizer, 1),
+                ],
+                "1/4 AC, 2 WA, 1 SAN, max time 7ms",
+            ),
+            (
+                vec![result("1", Verdict::TLE, 2000), result("2", Verdict::RE, 1)],
+                "0/2 AC, 1 RE, 1 TLE, max time 2000ms",
+            ),
+        ];
+        for (results, expected) in cases {
+            let line = summary_line(&results);
+            assert!(line.contains(expected), "{:?} / {}", line, expected);
+        }
+    }
+
+    #[test]
+    fn keeps_the_tail_of_long_stderr() {
+        assert_eq!(stderr_tail(""), "");
+        assert_eq!(stderr_tail("a\nb\n\n"), "a\nb");
+
+        let fits: Vec<String> = (1..=STDERR_TAIL_LINES).map(|i| i.to_string()).collect();
+        assert_eq!(stderr_tail(&fits.join("\n")), fits.join("\n"));
+
+        let long: Vec<String> = (1..=25).map(|i| i.to_string()).collect();
+        let tail = stderr_tail(&long.join("\n"));
+        let lines: Vec<&str> = tail.lines().collect();
+        assert_eq!(lines.len(), STDERR_TAIL_LINES + 1);
+        assert_eq!(lines[0], "... (5 行省略)");
+        assert_eq!(lines[1], "6");
+        assert_eq!(lines[STDERR_TAIL_LINES], "25");
+    }
+}
@@ -0,0 +1,358 @@
+use crate::config::StripConfig;
+use crate::lint;
+
+// ----------------------------------------------  //
+// 提出用のコードからデバッグ用のコードを取り除く
+// - #ifdef LOCAL ... #endif (#else 側は残す。#ifndef LOCAL は逆)
+// - // atk:debug-begin から // atk:debug-end までの行
+// - 文として書かれた dbg(...); の呼び出し
+// 取り除くマクロや目印は設定の [strip] で変更できる
+// ----------------------------------------------  //
+
+enum Frame {
+    // 取り除く対象でない #if (そのまま残す)
+    Other,
+    // #ifdef LOCAL などの #if。active はジャッジで有効な分岐にいるかどうか、
+    // taken はそれより前にジャッジで有効な分岐があったかどうか
+    Debug { active: bool, taken: bool },
+}
+
+fn is_visible(frames: &[Frame]) -> bool {
+    // ジャッジで有効な分岐の中にいるかどうか
+    frames.iter().all(|frame| match frame {
+        Frame::Other => true,
+        Frame::Debug { active, .. } => *active,
+    })
+}
+
+fn is_ident(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+fn skip_literal(bytes: &[u8], start: usize) -> usize {
+    // start にある " か ' で始まるリテラルの直後の位置を返す
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            i += 2;
+            continue;
+        }
+        if bytes[i] == quote {
+            return i + 1;
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+fn is_literal_start(bytes: &[u8], i: usize) -> bool {
+    // 1'000'000 の桁区切りはリテラルではない
+    match bytes[i] {
+        b'"' => true,
+        b'\'' => i == 0 || !bytes[i - 1].is_ascii_alphanumeric(),
+        _ => false,
+    }
+}
+
+fn call_statement_end(line: &str, start: usize, name: &str) -> Option<usize> {
+    /*
+    start から始まる "name(...);" の直後の位置を返す
+    呼び出しが1行に収まっていない場合や、文になっていない場合は None
+    */
+    let bytes = line.as_bytes();
+    if !line[start..].starts_with(name) {
+        return None;
+    }
+    let mut i = start + name.len();
+    if bytes.get(i).is_some_and(|&b| is_ident(b)) {
+        return None;
+    }
+    while bytes.get(i).is_some_and(|b| b.is_ascii_whitespace()) {
+        i += 1;
+    }
+    if bytes.get(i) != Some(&b'(') {
+        return None;
+    }
+    let mut depth = 0;
+    loop {
+        match bytes.get(i)? {
+            _ if is_literal_start(bytes, i) => {
+                i = skip_literal(bytes, i);
+                continue;
+            }
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    i += 1;
+                    break;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    while bytes.get(i).is_some_and(|b| b.is_ascii_whitespace()) {
+        i += 1;
+    }
+    (bytes.get(i) == Some(&b';')).then_some(i + 1)
+}
+
+fn remove_calls(line: &str, macros: &[String]) -> String {
+    /*
+    行から文として書かれたマクロの呼び出しを取り除く
+    "if (x) dbg(y);" のように取り除くと意味が変わるものは残す
+    */
+    let bytes = line.as_bytes();
+    let mut result = String::new();
+    let mut copied = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        // マルチバイト文字の途中では切り出せないので、文字の先頭まで進める
+        if !line.is_char_boundary(i) {
+            i += 1;
+            continue;
+        }
+        if is_literal_start(bytes, i) {
+            i = skip_literal(bytes, i);
+            continue;
+        }
+        if line[i..].starts_with("//") {
+            break;
+        }
+        let statement_start = (i == 0 || !is_ident(bytes[i - 1]))
+            && matches!(
+                line[..i].trim_end().bytes().last(),
+                None | Some(b';' | b'{' | b'}')
+            );
+        if statement_start
+            && let Some(end) = macros
+                .iter()
+                .find_map(|name| call_statement_end(line, i, name))
+        {
+            result.push_str(&line[copied..i]);
+            copied = end;
+            i = end;
+            continue;
+        }
+        i += 1;
+    }
+    if copied == 0 {
+        return line.to_string();
+    }
+    result.push_str(&line[copied..]);
+    result.trim_end().to_string()
+}
+
+fn strip_lines(source: &str, config: &StripConfig) -> anyhow::Result<Vec<Option<String>>> {
+    /*
+    各行を取り除いた結果 (取り除く行は None) を返す
+    */
+    let mut lines = Vec::new();
+    let mut frames: Vec<Frame> = Vec::new();
+    // 閉じていない目印 (終わりの目印と始まりの行番号)
+    let mut marker: Option<(&str, usize)> = None;
+
+    for (i, line) in source.lines().enumerate() {
+        if let Some((end, _)) = marker {
+            if line.trim_start().starts_with(end) {
+                marker = None;
+            }
+            lines.push(None);
+            continue;
+        }
+        if let Some(m) = config
+            .markers
+            .iter()
+            .find(|m| line.trim_start().starts_with(&m.begin))
+        {
+            marker = Some((&m.end, i + 1));
+            lines.push(None);
+            continue;
+        }
+
+        let outer_visible = is_visible(&frames[..frames.len().saturating_sub(1)]);
+        let visible = is_visible(&frames);
+        let Some((keyword, argument)) = lint::directive(lint::strip_comment(line)) else {
+            let stripped = remove_calls(line, &config.macros);
+            let removed = !visible || (stripped.trim().is_empty() && !line.trim().is_empty());
+            lines.push((!removed).then_some(stripped));
+            continue;
+        };
+        let kept = match (keyword, frames.last_mut()) {
+            ("if" | "ifdef" | "ifndef", _) => {
+                let defined = config
+                    .ifdef
+                    .iter()
+                    .find_map(|name| lint::defined_condition(keyword, argument, name));
+                match defined {
+                    // ジャッジでは定義されていないので、#ifdef なら偽、#ifndef なら真になる
+                    Some(defined) => {
+                        frames.push(Frame::Debug {
+                            active: !defined,
+                            taken: !defined,
+                        });
+                        None
+                    }
+                    None => {
+                        frames.push(Frame::Other);
+                        Some(line.to_string())
+                    }
+                }
+            }
+            ("elif", Some(frame @ Frame::Debug { taken: false, .. })) => {
+                // #ifdef LOCAL ... #elif X は #if X として残す
+                *frame = Frame::Other;
+                lines.push(outer_visible.then(|| format!("#if {}", argument)));
+                continue;
+            }
+            ("elif", Some(Frame::Debug { active, .. })) => {
+                *active = false;
+                None
+            }
+            ("else", Some(Frame::Debug { active, taken })) => {
+                *active = !*taken;
+                *taken = true;
+                None
+            }
+            ("endif", Some(Frame::Debug { .. })) => {
+                frames.pop();
+                None
+            }
+            ("endif", _) => {
+                frames.pop();
+                Some(line.to_string())
+            }
+            _ => Some(line.to_string()),
+        };
+        lines.push(kept.filter(|_| visible));
+    }
+
+    if let Some((_, begin)) = marker {
+        return Err(anyhow::anyhow!(
+            "展開後の {} 行目のデバッグ用の目印に対応する終わりの目印がありません",
+            begin
+        ));
+    }
+    Ok(lines)
+}
+
+fn join(lines: impl Iterator<Item = String>, source: &str) -> String {
+    let mut joined = lines.collect::<Vec<_>>().join("\n");
+    if source.ends_with('\n') {
+        joined.push('\n');
+    }
+    joined
+}
+
+pub fn strip(source: &str, config: &StripConfig) -> anyhow::Result<String> {
+    /*
+    提出用のコードからデバッグ用のコードを取り除く
+    args:
+        source: 元のコード
+        config: 取り除く対象の設定
+    returns:
+        デバッグ用のコードを取り除いたコード
+    */
+    if !config.enabled {
+        return Ok(source.to_string());
+    }
+    let lines = strip_lines(source, config)?;
+    Ok(join(lines.into_iter().flatten(), source))
+}
+
+pub fn strip_keeping_lines(source: &str, config: &StripConfig) -> anyhow::Result<String> {
+    /*
+    strip と同じだが、取り除いた行を空行にして行番号を元のコードと揃える
+    */
+    if !config.enabled {
+        return Ok(source.to_string());
+    }
+    let lines = strip_lines(source, config)?;
+    Ok(join(
+        lines.into_iter().map(Option::unwrap_or_default),
+        source,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip_default(source: &str) -> anyhow::Result<String> {
+        strip(source, &StripConfig::default())
+    }
+
+    #[test]
+    fn strips_local_branches() {
+        let cases = [
+            ("#ifdef LOCAL\na\n#endif\nb\n", "b\n"),
+            ("#ifdef LOCAL\na\n#else\nb\n#endif\n", "b\n"),
+            ("#ifndef LOCAL\na\n#else\nb\n#endif\n", "a\n"),
+            ("#if defined(LOCAL)\na\n#endif\nb\n", "b\n"),
+            (
+                "#ifdef LOCAL\na\n#elif X\nb\n#else\nc\n#endif\n",
+                "#if X\nb\n#else\nc\n#endif\n",
+            ),
+            ("#ifndef LOCAL\na\n#elif X\nb\n#endif\n", "a\n"),
+            (
+                "#if X\na\n#ifdef LOCAL\nb\n#endif\n#endif\n",
+                "#if X\na\n#endif\n",
+            ),
+            ("#ifdef LOCAL\n#if X\na\n#endif\n#endif\nb\n", "b\n"),
+            (
+                "#ifdef LOCAL_MAX\na\n#endif\n",
+                "#ifdef LOCAL_MAX\na\n#endif\n",
+            ),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(strip_default(source).unwrap(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn strips_markers_and_macro_calls() {
+        let cases = [
+            ("a\n// atk:debug-begin\nb\n// atk:debug-end\nc\n", "a\nc\n"),
+            ("  dbg(x);\ny();\n", "y();\n"),
+            ("x = 1; dbg(x, f(\")\")); y = 2;\n", "x = 1;  y = 2;\n"),
+            ("if (x) dbg(y);\n", "if (x) dbg(y);\n"),
+            ("int v = dbg(x);\n", "int v = dbg(x);\n"),
+            ("mydbg(x);\n", "mydbg(x);\n"),
+            ("dbg(x,\n  y);\n", "dbg(x,\n  y);\n"),
+            ("// dbg(x);\n", "// dbg(x);\n"),
+            ("int n = 1'000; dbg(n);\n", "int n = 1'000;\n"),
+            // コメントや文字列の中のマルチバイト文字
+            ("int n; /* 頂点数 */\n", "int n; /* 頂点数 */\n"),
+            ("int n; // 頂点数\n", "int n; // 頂点数\n"),
+            ("x = 1; dbg(x); // 頂点数\n", "x = 1;  // 頂点数\n"),
+            ("cout << \"答え\"; dbg(x);\n", "cout << \"答え\";\n"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(strip_default(source).unwrap(), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn keeps_line_numbers() {
+        let source = "#ifdef LOCAL\na\n#endif\nb; dbg(b);\n";
+        let config = StripConfig::default();
+        assert_eq!(strip_keeping_lines(source, &config).unwrap(), "\n\n\nb;\n");
+    }
+
+    #[test]
+    fn rejects_unclosed_markers() {
+        assert!(strip_default("a\n// atk:debug-begin\nb\n").is_err());
+    }
+
+    #[test]
+    fn can_be_disabled() {
+        let config = StripConfig {
+            enabled: false,
+            ..StripConfig::default()
+        };
+        let source = "#ifdef LOCAL\na\n#endif\n";
+        assert_eq!(strip(source, &config).unwrap(), source);
+    }
+}